@@ -0,0 +1,167 @@
+//! Maximum a posteriori decoding using the BCJR (forward-backward) algorithm.
+//!
+//! Instead of a single decoded sequence the decoder returns the posterior
+//! log-likelihood ratio ln(P(x=0|y) / P(x=1|y)) of every information bit.
+
+use std::f64;
+use super::Gens;
+use metric::{Metric, Bsc};
+use trellis::Trellis;
+
+/// Computes ln(e^a + e^b) without overflowing
+fn max_star(a: f64, b: f64) -> f64 {
+    if a == f64::NEG_INFINITY {
+        return b;
+    }
+    if b == f64::NEG_INFINITY {
+        return a;
+    }
+    a.max(b) + (-(a - b).abs()).exp().ln_1p()
+}
+
+/// Subtracts the largest value from all the values to keep them in range
+fn normalise(xs: &mut Vec<f64>) {
+    let max = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max != f64::NEG_INFINITY {
+        for x in xs.iter_mut() {
+            *x -= max;
+        }
+    }
+}
+
+/// Returns the posterior LLRs of the information bits of a hard-decision received word,
+/// `obs` is expected to be terminated like the output of `encode`.
+pub fn decode(obs: &Vec<u8>, gs: &Gens, p: f64) -> Vec<f64> {
+    decode_with(&Bsc::new(obs, p), gs)
+}
+
+/// Same as `decode` but the channel is described by an arbitrary metric
+pub fn decode_with<M: Metric>(metric: &M, gs: &Gens) -> Vec<f64> {
+    decode_apriori(metric, gs, &[])
+}
+
+/// Same as `decode_with` but with a priori LLRs of the information bits,
+/// as used in iterative decoding. An empty `apriori` means equiprobable bits.
+pub fn decode_apriori<M: Metric>(metric: &M, gs: &Gens, apriori: &[f64]) -> Vec<f64> {
+    let l = metric.len() / gs.n; // number of branches including the tail
+    let trellis = Trellis::new(gs);
+    let states = trellis.states();
+    assert!(apriori.is_empty() || apriori.len() == (l - gs.m) * gs.k);
+
+    // the branches (state, input symbol, next state, log-likelihood) at every depth
+    let mut gammas: Vec<Vec<(usize, usize, usize, f64)>> = Vec::with_capacity(l);
+    for i in 0..l {
+        let mut branches = Vec::with_capacity(states * trellis.inputs());
+        for s in 0..states {
+            for b in trellis.branches_at(s, i + gs.m >= l) {
+                let gamma: f64 = b.output
+                    .iter()
+                    .enumerate()
+                    .map(|(j, c)| metric.symbol(i * gs.n + j, *c))
+                    .sum();
+                // ln P(u) up to a constant is +-La/2 for every bit
+                let prior: f64 = if apriori.is_empty() || i + gs.m >= l {
+                    0f64
+                } else {
+                    (0..gs.k)
+                        .map(|j| {
+                            let la = apriori[i * gs.k + j];
+                            if (b.input >> j) & 1 == 0 { la / 2f64 } else { -la / 2f64 }
+                        })
+                        .sum()
+                };
+                branches.push((s, b.input, b.to, gamma * f64::consts::LN_2 + prior));
+            }
+        }
+        gammas.push(branches);
+    }
+
+    // forward recursion, the encoder starts in the zero state
+    let mut alphas = vec![vec![f64::NEG_INFINITY; states]; l + 1];
+    alphas[0][0] = 0f64;
+    for i in 0..l {
+        let mut next = vec![f64::NEG_INFINITY; states];
+        for &(s, _, t, gamma) in &gammas[i] {
+            next[t] = max_star(next[t], alphas[i][s] + gamma);
+        }
+        normalise(&mut next);
+        alphas[i + 1] = next;
+    }
+
+    // backward recursion, the tail forces the encoder back to the zero state
+    let mut betas = vec![vec![f64::NEG_INFINITY; states]; l + 1];
+    betas[l][0] = 0f64;
+    for i in (0..l).rev() {
+        let mut prev = vec![f64::NEG_INFINITY; states];
+        for &(s, _, t, gamma) in &gammas[i] {
+            prev[s] = max_star(prev[s], gamma + betas[i + 1][t]);
+        }
+        normalise(&mut prev);
+        betas[i] = prev;
+    }
+
+    // combine both recursions for every information bit, the tail carries no information
+    let mut llrs = Vec::with_capacity((l - gs.m) * gs.k);
+    for i in 0..l - gs.m {
+        let mut pr = vec![[f64::NEG_INFINITY; 2]; gs.k];
+        for &(s, u, t, gamma) in &gammas[i] {
+            let app = alphas[i][s] + gamma + betas[i + 1][t];
+            for (b, pr) in pr.iter_mut().enumerate() {
+                let x = (u >> b) & 1;
+                pr[x] = max_star(pr[x], app);
+            }
+        }
+        llrs.extend(pr.iter().map(|pr| pr[0] - pr[1]));
+    }
+    llrs
+}
+
+/// Converts LLRs into hard decisions, ties are decided as 0
+pub fn hard_decision(llrs: &[f64]) -> Vec<u8> {
+    llrs.iter().map(|&l| if l >= 0f64 { 0 } else { 1 }).collect()
+}
+
+#[test]
+fn test_bcjr_and_viterbi() {
+    use super::{encode, viterbi};
+
+    let xs = vec![1, 1, 0, 1, 0, 0, 1, 0, 1, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let mut ys = encode(&xs, &gs);
+    let llrs = decode(&ys, &gs, 0.1);
+    assert_eq!(llrs.len(), xs.len());
+    assert_eq!(hard_decision(&llrs), xs);
+
+    ys[1] ^= 1;
+    ys[14] ^= 1;
+    let noisy_llrs = decode(&ys, &gs, 0.1);
+    assert_eq!(hard_decision(&noisy_llrs), viterbi::decode(&ys, &gs, 0.1));
+
+    // the errors make the decoder less confident
+    for (a, b) in llrs.iter().zip(noisy_llrs.iter()) {
+        assert!(a.abs() + 1e-9 >= b.abs());
+    }
+}
+
+#[test]
+fn test_bcjr_no_information() {
+    use metric::Llr;
+
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let llrs = decode_with(&Llr::new(&[0f64; 18]), &gs);
+    assert_eq!(llrs.len(), 4);
+    assert!(llrs.iter().all(|l| l.abs() < 1e-9));
+}
+
+#[test]
+fn test_bcjr_apriori() {
+    use metric::Llr;
+
+    // without channel information the posterior is the a priori information
+    let gs = Gens::systematic(vec![1, 1, 1], vec![vec![1, 0, 1]]);
+    let apriori = vec![2f64, -3f64, 0.5f64, -1f64];
+    let llrs = decode_apriori(&Llr::new(&[0f64; 12]), &gs, &apriori);
+    for (a, b) in llrs.iter().zip(apriori.iter()) {
+        assert!((a - b).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,267 @@
+//! A bit-packed vector of binary symbols.
+//!
+//! The public interface mostly stores every bit as a `u8` that is 0 or 1. `BitVec` packs 64
+//! bits into a word, which saves memory on long frames and on the many paths of the tree
+//! search decoders, and it lets XOR and weight computations work on whole words. The encoder
+//! and the stack, Fano and streaming decoders keep their paths packed, while Viterbi and BCJR
+//! work on the trellis tables. It converts to and from the `Vec<u8>` representation, where
+//! the first element is bit 0.
+
+use std::fmt;
+
+const WORD: usize = 64;
+
+/// A growable vector of bits, bit i is stored in bit `i % 64` of word `i / 64`
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> BitVec {
+        BitVec::default()
+    }
+
+    pub fn with_capacity(bits: usize) -> BitVec {
+        BitVec {
+            words: Vec::with_capacity((bits + WORD - 1) / WORD),
+            len: 0,
+        }
+    }
+
+    /// Packs the bits of `xs`, which must all be 0 or 1
+    pub fn from_bits(xs: &[u8]) -> BitVec {
+        let mut res = BitVec::with_capacity(xs.len());
+        for &x in xs {
+            res.push(x);
+        }
+        res
+    }
+
+    /// Unpacks the bits into one `u8` per bit
+    pub fn to_bits(&self) -> Vec<u8> {
+        self.iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The bit at position `i` as 0 or 1
+    pub fn get(&self, i: usize) -> u8 {
+        assert!(i < self.len, "index {} out of range for {} bits", i, self.len);
+        ((self.words[i / WORD] >> (i % WORD)) & 1) as u8
+    }
+
+    pub fn set(&mut self, i: usize, x: u8) {
+        assert!(i < self.len, "index {} out of range for {} bits", i, self.len);
+        assert!(x == 0 || x == 1);
+        let mask = 1 << (i % WORD);
+        if x == 1 {
+            self.words[i / WORD] |= mask;
+        } else {
+            self.words[i / WORD] &= !mask;
+        }
+    }
+
+    pub fn push(&mut self, x: u8) {
+        assert!(x == 0 || x == 1);
+        if self.len % WORD == 0 {
+            self.words.push(0);
+        }
+        self.words[self.len / WORD] |= (x as u64) << (self.len % WORD);
+        self.len += 1;
+    }
+
+    /// Appends the lowest `bits` bits of `word`, bit 0 first.
+    /// With `k` bits this appends an input symbol, bit i being the bit of input i.
+    pub fn push_word(&mut self, word: u64, bits: usize) {
+        assert!(bits <= WORD);
+        if bits == 0 {
+            return;
+        }
+        let word = if bits < WORD { word & ((1 << bits) - 1) } else { word };
+        let offset = self.len % WORD;
+        if offset == 0 {
+            self.words.push(word);
+        } else {
+            *self.words.last_mut().unwrap() |= word << offset;
+            if offset + bits > WORD {
+                self.words.push(word >> (WORD - offset));
+            }
+        }
+        self.len += bits;
+    }
+
+    /// Reads `bits` bits from position `start` into a word, the inverse of `push_word`
+    pub fn word(&self, start: usize, bits: usize) -> u64 {
+        assert!(bits <= WORD && start + bits <= self.len);
+        if bits == 0 {
+            return 0;
+        }
+        let (idx, offset) = (start / WORD, start % WORD);
+        let mut res = self.words[idx] >> offset;
+        if offset + bits > WORD {
+            res |= self.words[idx + 1] << (WORD - offset);
+        }
+        if bits < WORD { res & ((1 << bits) - 1) } else { res }
+    }
+
+    pub fn extend_from_bits(&mut self, xs: &[u8]) {
+        for &x in xs {
+            self.push(x);
+        }
+    }
+
+    /// Shortens the vector to `len` bits, does nothing if it is already shorter
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        self.words.truncate((len + WORD - 1) / WORD);
+        if len % WORD != 0 {
+            *self.words.last_mut().unwrap() &= (1 << (len % WORD)) - 1;
+        }
+        self.len = len;
+    }
+
+    /// Removes the first `bits` bits and shifts the rest to the front
+    pub fn remove_front(&mut self, bits: usize) {
+        assert!(bits <= self.len);
+        let mut res = BitVec::with_capacity(self.len - bits);
+        let mut i = bits;
+        while i < self.len {
+            let step = ::std::cmp::min(WORD, self.len - i);
+            res.push_word(self.word(i, step), step);
+            i += step;
+        }
+        *self = res;
+    }
+
+    /// The number of ones, i.e. the Hamming weight
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// XORs `other` into this vector word by word, both must have the same length
+    pub fn xor_with(&mut self, other: &BitVec) {
+        assert_eq!(self.len, other.len);
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a ^= *b;
+        }
+    }
+
+    /// The number of positions where the vectors differ, both must have the same length
+    pub fn distance(&self, other: &BitVec) -> usize {
+        assert_eq!(self.len, other.len);
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the bits as 0 or 1
+    pub fn iter<'a>(&'a self) -> Iter<'a> {
+        Iter { bits: self, pos: 0 }
+    }
+}
+
+impl<'a> From<&'a [u8]> for BitVec {
+    fn from(xs: &'a [u8]) -> BitVec {
+        BitVec::from_bits(xs)
+    }
+}
+
+impl From<Vec<u8>> for BitVec {
+    fn from(xs: Vec<u8>) -> BitVec {
+        BitVec::from_bits(&xs)
+    }
+}
+
+impl From<BitVec> for Vec<u8> {
+    fn from(xs: BitVec) -> Vec<u8> {
+        xs.to_bits()
+    }
+}
+
+/// Shows the bits as a string of 0 and 1, i.e. `BitVec(0110)`
+impl fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bits: String = self.iter().map(|x| if x == 1 { '1' } else { '0' }).collect();
+        write!(f, "BitVec({})", bits)
+    }
+}
+
+/// An iterator over the bits of a `BitVec`
+pub struct Iter<'a> {
+    bits: &'a BitVec,
+    pos: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.pos == self.bits.len {
+            return None;
+        }
+        self.pos += 1;
+        Some(self.bits.get(self.pos - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.bits.len - self.pos;
+        (rest, Some(rest))
+    }
+}
+
+#[test]
+fn test_bitvec() {
+    let xs: Vec<u8> = (0..150).map(|i| (i % 3 == 0 || i % 7 == 0) as u8).collect();
+    let bits = BitVec::from_bits(&xs);
+    assert_eq!(bits.len(), 150);
+    assert_eq!(bits.to_bits(), xs);
+    assert_eq!(Vec::from(bits.clone()), xs);
+    assert_eq!(bits.count_ones(), xs.iter().filter(|&&x| x == 1).count());
+    assert_eq!(format!("{:?}", BitVec::from(vec![0, 1, 1])), "BitVec(011)");
+
+    // words across the boundary of the storage
+    let mut ys = BitVec::new();
+    ys.push_word(0b101, 3);
+    ys.push_word(u64::max_value(), 64);
+    ys.push_word(0b10, 2);
+    assert_eq!(ys.len(), 69);
+    assert_eq!(ys.word(0, 3), 0b101);
+    assert_eq!(ys.word(3, 64), u64::max_value());
+    assert_eq!(ys.word(66, 3), 0b101);
+
+    let mut zs = ys.clone();
+    zs.remove_front(2);
+    assert_eq!((zs.len(), zs.word(0, 1), zs.word(1, 64)), (67, 1, u64::max_value()));
+    zs.remove_front(67);
+    assert!(zs.is_empty());
+
+    ys.truncate(4);
+    assert_eq!(ys.to_bits(), vec![1, 0, 1, 1]);
+    ys.push(0);
+    ys.set(1, 1);
+    assert_eq!(ys, BitVec::from_bits(&[1, 1, 1, 1, 0]));
+}
+
+#[test]
+fn test_bitvec_xor() {
+    let xs = BitVec::from_bits(&[1; 100]);
+    let mut ys = BitVec::from_bits(&[0; 100]);
+    ys.set(3, 1);
+    ys.set(70, 1);
+    assert_eq!(xs.distance(&ys), 98);
+    ys.xor_with(&xs);
+    assert_eq!(ys.count_ones(), 98);
+    assert_eq!((ys.get(3), ys.get(70), ys.get(99)), (0, 0, 1));
+}
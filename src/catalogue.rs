@@ -0,0 +1,166 @@
+//! A catalogue of well-known convolutional codes.
+//!
+//! The generators are written in the octal notation of `parse_gs` as they appear in the
+//! standards and textbooks, i.e. `K=7:171,133`.
+
+use super::{Gens, parse_gs};
+
+/// A named code of the catalogue
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Code {
+    pub name: &'static str,
+    /// The generators in the notation of `parse_gs`
+    pub gs: &'static str,
+    /// The number of inputs k of the rate k/n
+    pub k: usize,
+    /// The number of outputs n of the rate k/n
+    pub n: usize,
+    pub memory: usize,
+    pub dfree: usize,
+    /// Whether the standard uses the code in tail-biting mode instead of terminating it
+    pub tail_biting: bool,
+    /// Where the code is defined
+    pub source: &'static str,
+}
+
+impl Code {
+    pub fn gens(&self) -> Gens {
+        // the catalogue is checked by the tests
+        parse_gs(self.gs).unwrap()
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.k as f64 / self.n as f64
+    }
+}
+
+pub const CODES: &'static [Code] = &[
+    Code {
+        name: "NASA/CCSDS K=7",
+        gs: "K=7:171,133",
+        k: 1,
+        n: 2,
+        memory: 6,
+        dfree: 10,
+        tail_biting: false,
+        source: "CCSDS 131.0-B, TM synchronization and channel coding",
+    },
+    Code {
+        name: "GSM K=5 rate 1/2",
+        gs: "K=5:23,33",
+        k: 1,
+        n: 2,
+        memory: 4,
+        dfree: 7,
+        tail_biting: false,
+        source: "3GPP TS 45.003, full rate speech channel",
+    },
+    Code {
+        name: "GSM K=5 rate 1/3",
+        gs: "K=5:33,25,37",
+        k: 1,
+        n: 3,
+        memory: 4,
+        dfree: 12,
+        tail_biting: false,
+        source: "3GPP TS 45.003, 4.8 kbit/s data channel",
+    },
+    Code {
+        name: "IEEE 802.11 mother code",
+        gs: "K=7:133,171",
+        k: 1,
+        n: 2,
+        memory: 6,
+        dfree: 10,
+        tail_biting: false,
+        source: "IEEE 802.11, OFDM PHY convolutional encoder",
+    },
+    Code {
+        name: "LTE tail-biting rate 1/3",
+        gs: "K=7:133,171,165",
+        k: 1,
+        n: 3,
+        memory: 6,
+        dfree: 15,
+        tail_biting: true,
+        source: "3GPP TS 36.212, tail biting convolutional coding",
+    },
+    Code {
+        name: "Textbook K=3 rate 1/2",
+        gs: "K=3:7,5",
+        k: 1,
+        n: 2,
+        memory: 2,
+        dfree: 5,
+        tail_biting: false,
+        source: "Lin and Costello, Error Control Coding",
+    },
+    Code {
+        name: "Textbook K=4 rate 1/2",
+        gs: "K=4:15,17",
+        k: 1,
+        n: 2,
+        memory: 3,
+        dfree: 6,
+        tail_biting: false,
+        source: "Lin and Costello, Error Control Coding",
+    },
+    Code {
+        name: "Textbook K=5 rate 1/2",
+        gs: "K=5:23,35",
+        k: 1,
+        n: 2,
+        memory: 4,
+        dfree: 7,
+        tail_biting: false,
+        source: "Lin and Costello, Error Control Coding",
+    },
+    Code {
+        name: "Textbook K=6 rate 1/2",
+        gs: "K=6:53,75",
+        k: 1,
+        n: 2,
+        memory: 5,
+        dfree: 8,
+        tail_biting: false,
+        source: "Lin and Costello, Error Control Coding",
+    },
+    Code {
+        name: "Textbook K=3 rate 1/3",
+        gs: "K=3:5,7,7",
+        k: 1,
+        n: 3,
+        memory: 2,
+        dfree: 8,
+        tail_biting: false,
+        source: "Lin and Costello, Error Control Coding",
+    },
+    Code {
+        name: "Textbook K=4 rate 1/3",
+        gs: "K=4:13,15,17",
+        k: 1,
+        n: 3,
+        memory: 3,
+        dfree: 10,
+        tail_biting: false,
+        source: "Lin and Costello, Error Control Coding",
+    },
+];
+
+/// Looks up a code of the catalogue by its name
+pub fn find(name: &str) -> Option<&'static Code> {
+    CODES.iter().find(|c| c.name == name)
+}
+
+#[test]
+fn test_catalogue() {
+    use distance::free_distance;
+
+    for code in CODES {
+        let gs = code.gens();
+        assert_eq!((gs.k, gs.n, gs.memory()), (code.k, code.n, code.memory), "{}", code.name);
+        assert_eq!(free_distance(&gs), code.dfree, "{}", code.name);
+    }
+    assert_eq!(find("NASA/CCSDS K=7").unwrap().rate(), 0.5);
+    assert_eq!(find("Nonexistent"), None);
+}
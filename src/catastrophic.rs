@@ -0,0 +1,182 @@
+//! Detection of catastrophic encoders.
+//!
+//! An encoder is catastrophic when an input of infinite weight produces a code of finite
+//! weight, so a finite number of channel errors can cause an infinite number of decoding
+//! errors. By the theorem of Massey and Sain this is the case unless the greatest common
+//! divisor of the k x k minors of the generator matrix is a power of x. For a recursive
+//! encoder the factors shared with the feedback polynomial cancel and are harmless.
+//!
+//! Polynomials are coefficient vectors over GF(2), the first element is the coefficient of x^0.
+
+use super::{Gens, poly_mul};
+
+/// Removes the zero coefficients of the highest powers, the zero polynomial is empty
+fn trim(mut a: Vec<u8>) -> Vec<u8> {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+    a
+}
+
+fn poly_add(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut c = vec![0; ::std::cmp::max(a.len(), b.len())];
+    for (i, x) in a.iter().enumerate() {
+        c[i] ^= *x;
+    }
+    for (i, y) in b.iter().enumerate() {
+        c[i] ^= *y;
+    }
+    trim(c)
+}
+
+/// Polynomial division, returns the quotient and the remainder
+fn poly_div(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let b = trim(b.to_vec());
+    assert!(!b.is_empty(), "division by the zero polynomial");
+    let mut rem = trim(a.to_vec());
+    if rem.len() < b.len() {
+        return (Vec::new(), rem);
+    }
+    let mut quot = vec![0; rem.len() - b.len() + 1];
+    while rem.len() >= b.len() {
+        let shift = rem.len() - b.len();
+        quot[shift] = 1;
+        for (i, y) in b.iter().enumerate() {
+            rem[i + shift] ^= *y;
+        }
+        rem = trim(rem);
+    }
+    (trim(quot), rem)
+}
+
+fn poly_gcd(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (mut a, mut b) = (trim(a.to_vec()), trim(b.to_vec()));
+    while !b.is_empty() {
+        let rem = poly_div(&a, &b).1;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
+/// The determinant of a square matrix of polynomials by Laplace expansion along the first row
+fn det(matrix: &[Vec<Vec<u8>>]) -> Vec<u8> {
+    if matrix.len() == 1 {
+        return trim(matrix[0][0].clone());
+    }
+    let mut res = Vec::new();
+    for j in 0..matrix.len() {
+        let minor: Vec<Vec<Vec<u8>>> = matrix[1..]
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(c, _)| c != j)
+                    .map(|(_, g)| g.clone())
+                    .collect()
+            })
+            .collect();
+        // signs do not matter in GF(2)
+        res = poly_add(&res, &poly_mul(&matrix[0][j], &det(&minor)));
+    }
+    res
+}
+
+/// All the subsets of `k` columns out of `n`, in increasing order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    let mut res = Vec::new();
+    for last in k - 1..n {
+        for mut c in combinations(last, k - 1) {
+            c.push(last);
+            res.push(c);
+        }
+    }
+    res
+}
+
+/// The common factor of the generators that makes an encoder catastrophic,
+/// i.e. the GCD of the k x k minors without its factors x and without the factors it
+/// shares with the feedback polynomial. The encoder is not catastrophic if this is 1.
+/// The zero polynomial (an empty vector) means that the encoder is not even invertible.
+pub fn common_factor(gs: &Gens) -> Vec<u8> {
+    let mut g = Vec::new();
+    for cols in combinations(gs.n, gs.k) {
+        let matrix: Vec<Vec<Vec<u8>>> = (0..gs.k)
+            .map(|i| cols.iter().map(|&j| gs.gen(i, j).clone()).collect())
+            .collect();
+        g = poly_gcd(&g, &det(&matrix));
+    }
+    if g.is_empty() {
+        return g;
+    }
+
+    if let Some(ref f) = gs.fb {
+        let shared = poly_gcd(&g, f);
+        g = poly_div(&g, &shared).0;
+    }
+    while g[0] == 0 {
+        g.remove(0);
+    }
+    g
+}
+
+/// Whether a finite number of channel errors can cause an infinite number of decoding errors
+pub fn is_catastrophic(gs: &Gens) -> bool {
+    common_factor(gs) != vec![1]
+}
+
+/// Formats a polynomial as i.e. 1 + x + x^3
+pub fn format_poly(g: &[u8]) -> String {
+    let terms: Vec<String> = g.iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == 1)
+        .map(|(i, _)| match i {
+            0 => "1".to_string(),
+            1 => "x".to_string(),
+            _ => format!("x^{}", i),
+        })
+        .collect();
+    if terms.is_empty() { "0".to_string() } else { terms.join(" + ") }
+}
+
+#[test]
+fn test_poly() {
+    // (1 + x)(1 + x + x^2) = 1 + x^3
+    assert_eq!(poly_div(&[1, 0, 0, 1], &[1, 1]), (vec![1, 1, 1], vec![]));
+    assert_eq!(poly_div(&[1, 0, 1, 1], &[1, 1]), (vec![0, 0, 1], vec![1]));
+    assert_eq!(poly_gcd(&[1, 0, 0, 1], &[1, 0, 1]), vec![1, 1]);
+    assert_eq!(poly_gcd(&[1, 1, 1], &[1, 0, 1]), vec![1]);
+    assert_eq!(combinations(3, 2), vec![vec![0, 1], vec![0, 2], vec![1, 2]]);
+    assert_eq!(format_poly(&[1, 1, 0, 1]), "1 + x + x^3");
+}
+
+#[test]
+fn test_catastrophic() {
+    assert!(!is_catastrophic(&Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]])));
+    // 1 + x divides both generators
+    let gs = Gens::new(vec![vec![1, 1], vec![1, 1]]);
+    assert_eq!(common_factor(&gs), vec![1, 1]);
+    let gs = Gens::new(vec![vec![1, 1, 0], vec![1, 0, 1]]);
+    assert_eq!(common_factor(&gs), vec![1, 1]);
+    // a factor x only delays the code
+    assert!(!is_catastrophic(&Gens::new(vec![vec![0, 1, 1], vec![0, 1, 0]])));
+
+    // systematic encoders are never catastrophic
+    assert!(!is_catastrophic(&Gens::systematic(vec![1, 1], vec![vec![1, 1]])));
+    // the common factor 1 + x of the numerators cancels with the feedback 1 + x^2
+    let gs = Gens::recursive(vec![1, 0, 1, 0], vec![vec![1, 1, 0, 0], vec![1, 0, 0, 1]]);
+    assert!(!is_catastrophic(&gs));
+    // but not with the feedback 1 + x + x^2
+    let gs = Gens::recursive(vec![1, 1, 1, 0], vec![vec![1, 0, 1, 0], vec![1, 0, 0, 1]]);
+    assert_eq!(common_factor(&gs), vec![1, 1]);
+
+    // rate 2/3, the minors are 1 + x, 1 + x and 1
+    let gs = Gens::with_inputs(vec![vec![vec![1, 1], vec![0, 0], vec![1, 0]],
+                                    vec![vec![0], vec![1], vec![1]]]);
+    assert!(!is_catastrophic(&gs));
+    let gs = Gens::with_inputs(vec![vec![vec![1, 1], vec![1, 1]], vec![vec![1, 0], vec![1, 0]]]);
+    assert_eq!(common_factor(&gs), vec![]);
+}
@@ -0,0 +1,282 @@
+//! Channel models for simulations.
+//!
+//! A `Channel` transmits code bits and returns its own kind of received symbols.
+//! Every channel also converts its symbols into log-likelihood ratios, which is all the
+//! decoders need: the metric of any binary input channel follows from the LLR,
+//! so `metric::Llr::new(&channel.llrs(&ys))` is the matching decoder metric.
+
+use std::f64;
+use rand::Rng;
+use rand::distributions::{Normal, IndependentSample};
+
+/// The magnitude used for LLRs of symbols that can only come from one code bit,
+/// it keeps the metrics finite
+pub const MAX_LLR: f64 = 100f64;
+
+/// A binary input channel
+pub trait Channel {
+    /// The received symbol
+    type Symbol;
+
+    /// Sends the code bits `xs` over the channel
+    fn transmit<R: Rng>(&mut self, xs: &[u8], rng: &mut R) -> Vec<Self::Symbol>;
+
+    /// The log-likelihood ratio ln(P(y|0) / P(y|1)) of a received symbol
+    fn llr(&self, y: &Self::Symbol) -> f64;
+
+    /// The log-likelihood ratios of the received symbols, to be used with `metric::Llr`
+    fn llrs(&self, ys: &[Self::Symbol]) -> Vec<f64> {
+        ys.iter().map(|y| self.llr(y)).collect()
+    }
+}
+
+/// Flips a bit with probability `p`
+fn flip<R: Rng>(x: u8, p: f64, rng: &mut R) -> u8 {
+    if rng.gen::<f64>() < p { 1 - x } else { x }
+}
+
+/// Binary symmetric channel with crossover probability `p`
+#[derive(Clone, Copy, Debug)]
+pub struct Bsc {
+    pub p: f64,
+}
+
+impl Bsc {
+    pub fn new(p: f64) -> Bsc {
+        assert!(p > 0f64 && p < 1f64);
+        Bsc { p: p }
+    }
+}
+
+impl Channel for Bsc {
+    type Symbol = u8;
+
+    fn transmit<R: Rng>(&mut self, xs: &[u8], rng: &mut R) -> Vec<u8> {
+        xs.iter().map(|&x| flip(x, self.p, rng)).collect()
+    }
+
+    fn llr(&self, y: &u8) -> f64 {
+        let l = ((1f64 - self.p) / self.p).ln();
+        if *y == 0 { l } else { -l }
+    }
+}
+
+/// Binary erasure channel, every bit is erased (`None`) with probability `e`
+#[derive(Clone, Copy, Debug)]
+pub struct Bec {
+    pub e: f64,
+}
+
+impl Bec {
+    pub fn new(e: f64) -> Bec {
+        assert!(e >= 0f64 && e < 1f64);
+        Bec { e: e }
+    }
+}
+
+impl Channel for Bec {
+    type Symbol = Option<u8>;
+
+    fn transmit<R: Rng>(&mut self, xs: &[u8], rng: &mut R) -> Vec<Option<u8>> {
+        xs.iter().map(|&x| if rng.gen::<f64>() < self.e { None } else { Some(x) }).collect()
+    }
+
+    fn llr(&self, y: &Option<u8>) -> f64 {
+        match *y {
+            None => 0f64,
+            Some(0) => MAX_LLR,
+            Some(_) => -MAX_LLR,
+        }
+    }
+}
+
+/// BPSK over an additive white Gaussian noise channel, 0 is sent as +1 and 1 as -1
+#[derive(Clone, Copy, Debug)]
+pub struct Awgn {
+    /// The standard deviation of the noise
+    pub sigma: f64,
+}
+
+impl Awgn {
+    /// The channel for the given Eb/N0 in dB and code rate, Es/N0 = rate * Eb/N0
+    pub fn new(ebn0_db: f64, rate: f64) -> Awgn {
+        assert!(rate > 0f64);
+        let esn0 = rate * 10f64.powf(ebn0_db / 10f64);
+        Awgn::with_sigma((0.5f64 / esn0).sqrt())
+    }
+
+    pub fn with_sigma(sigma: f64) -> Awgn {
+        assert!(sigma > 0f64);
+        Awgn { sigma: sigma }
+    }
+}
+
+impl Channel for Awgn {
+    type Symbol = f64;
+
+    fn transmit<R: Rng>(&mut self, xs: &[u8], rng: &mut R) -> Vec<f64> {
+        let noise = Normal::new(0f64, self.sigma);
+        xs.iter()
+            .map(|&x| if x == 0 { 1f64 } else { -1f64 } + noise.ind_sample(rng))
+            .collect()
+    }
+
+    fn llr(&self, y: &f64) -> f64 {
+        2f64 * y / (self.sigma * self.sigma)
+    }
+}
+
+/// Z-channel, a 0 is always received correctly and a 1 turns into a 0 with probability `p`
+#[derive(Clone, Copy, Debug)]
+pub struct ZChannel {
+    pub p: f64,
+}
+
+impl ZChannel {
+    pub fn new(p: f64) -> ZChannel {
+        assert!(p > 0f64 && p < 1f64);
+        ZChannel { p: p }
+    }
+}
+
+impl Channel for ZChannel {
+    type Symbol = u8;
+
+    fn transmit<R: Rng>(&mut self, xs: &[u8], rng: &mut R) -> Vec<u8> {
+        xs.iter().map(|&x| if x == 1 { flip(x, self.p, rng) } else { x }).collect()
+    }
+
+    fn llr(&self, y: &u8) -> f64 {
+        if *y == 0 { (1f64 / self.p).ln() } else { -MAX_LLR }
+    }
+}
+
+/// Gilbert-Elliott burst channel, a BSC whose crossover probability depends on
+/// a hidden good or bad state that changes after every bit
+#[derive(Clone, Copy, Debug)]
+pub struct GilbertElliott {
+    /// The crossover probability in the good state
+    pub p_good: f64,
+    /// The crossover probability in the bad state
+    pub p_bad: f64,
+    /// The probability of moving from the good to the bad state
+    pub to_bad: f64,
+    /// The probability of moving from the bad to the good state
+    pub to_good: f64,
+    /// Whether the channel is currently in the bad state, kept between transmissions
+    pub bad: bool,
+}
+
+impl GilbertElliott {
+    /// The channel starts in the good state
+    pub fn new(p_good: f64, p_bad: f64, to_bad: f64, to_good: f64) -> GilbertElliott {
+        assert!(p_good >= 0f64 && p_good < 1f64 && p_bad >= 0f64 && p_bad < 1f64);
+        assert!(to_bad > 0f64 && to_bad <= 1f64 && to_good > 0f64 && to_good <= 1f64);
+        GilbertElliott {
+            p_good: p_good,
+            p_bad: p_bad,
+            to_bad: to_bad,
+            to_good: to_good,
+            bad: false,
+        }
+    }
+
+    /// The average crossover probability in the steady state
+    pub fn mean_p(&self) -> f64 {
+        let pi_bad = self.to_bad / (self.to_bad + self.to_good);
+        pi_bad * self.p_bad + (1f64 - pi_bad) * self.p_good
+    }
+}
+
+impl Channel for GilbertElliott {
+    type Symbol = u8;
+
+    fn transmit<R: Rng>(&mut self, xs: &[u8], rng: &mut R) -> Vec<u8> {
+        let mut ys = Vec::with_capacity(xs.len());
+        for &x in xs {
+            let p = if self.bad { self.p_bad } else { self.p_good };
+            ys.push(flip(x, p, rng));
+            let change = if self.bad { self.to_good } else { self.to_bad };
+            if rng.gen::<f64>() < change {
+                self.bad = !self.bad;
+            }
+        }
+        ys
+    }
+
+    /// The decoders assume a memoryless channel, so the LLR is that of a BSC
+    /// with the average crossover probability
+    fn llr(&self, y: &u8) -> f64 {
+        let p = self.mean_p().max(f64::MIN_POSITIVE);
+        let l = ((1f64 - p) / p).ln().min(MAX_LLR);
+        if *y == 0 { l } else { -l }
+    }
+}
+
+#[test]
+fn test_channels() {
+    use metric::{self, Metric};
+    use super::seeded_rng;
+    const CNT: usize = 20000;
+    let mut rng = seeded_rng(14);
+    let zeros = vec![0; CNT];
+    let ones = vec![1; CNT];
+    let count = |ys: &[u8], x: u8| ys.iter().filter(|&&y| y == x).count() as f64 / CNT as f64;
+
+    let mut bsc = Bsc::new(0.1);
+    assert!((count(&bsc.transmit(&zeros, &mut rng), 1) - 0.1).abs() < 0.01);
+    // the LLR metric of the BSC equals the hard decision metric
+    let ys = vec![0, 1];
+    let (hard, soft) = (metric::Bsc::new(&ys, 0.1), bsc.llrs(&ys));
+    for i in 0..2 {
+        for x in 0..2 {
+            assert!((hard.symbol(i, x) - metric::Llr::new(&soft).symbol(i, x)).abs() < 1e-9);
+        }
+    }
+
+    let mut bec = Bec::new(0.3);
+    let ys = bec.transmit(&ones, &mut rng);
+    assert!(ys.iter().all(|&y| y != Some(0)));
+    let erased = ys.iter().filter(|y| y.is_none()).count() as f64 / CNT as f64;
+    assert!((erased - 0.3).abs() < 0.02);
+    assert_eq!(bec.llr(&None), 0f64);
+
+    // at 0 dB and rate 1/2 the noise variance is 1
+    let mut awgn = Awgn::new(0f64, 0.5);
+    assert!((awgn.sigma - 1f64).abs() < 1e-9);
+    let ys = awgn.transmit(&zeros, &mut rng);
+    let mean = ys.iter().sum::<f64>() / CNT as f64;
+    assert!((mean - 1f64).abs() < 0.05);
+    assert_eq!(awgn.llr(&0.5), 1f64);
+
+    let mut z = ZChannel::new(0.2);
+    assert_eq!(z.transmit(&zeros, &mut rng), zeros);
+    assert!((count(&z.transmit(&ones, &mut rng), 0) - 0.2).abs() < 0.02);
+    assert_eq!(z.llr(&1), -MAX_LLR);
+
+    // errors only happen in the bad state, which lasts 10 bits on average
+    let mut ge = GilbertElliott::new(0f64, 0.5, 0.01, 0.1);
+    assert!((ge.mean_p() - 0.5 / 11f64).abs() < 1e-9);
+    let ys = ge.transmit(&zeros, &mut rng);
+    let bursts = ys.windows(2).filter(|w| w[0] == 1 && w[1] == 1).count();
+    assert!(bursts > 0);
+    assert_eq!(ge.llr(&0), -ge.llr(&1));
+}
+
+#[test]
+fn test_channel_decode() {
+    // a good channel of every kind decodes without errors
+    use super::{Gens, encode, decode_soft, seeded_rng};
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let xs = vec![1, 0, 1, 1, 0, 0, 1, 0];
+    let code = encode(&xs, &gs);
+    let mut rng = seeded_rng(1);
+
+    let mut awgn = Awgn::new(20f64, 0.5);
+    let ys = awgn.transmit(&code, &mut rng);
+    assert_eq!(decode_soft(&awgn.llrs(&ys), &gs), xs);
+
+    let mut z = ZChannel::new(1e-9);
+    let ys = z.transmit(&code, &mut rng);
+    assert_eq!(decode_soft(&z.llrs(&ys), &gs), xs);
+}
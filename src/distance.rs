@@ -0,0 +1,205 @@
+//! Distance properties of convolutional codes.
+//!
+//! An error event leaves the zero state with a nonzero input and returns to it later.
+//! The free distance is the smallest output weight of an error event, and the weight
+//! spectrum counts the error events and their information weights at every distance.
+
+use super::Gens;
+use metric::erfc;
+use trellis::Trellis;
+
+/// The error events of one distance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Term {
+    /// The output weight of the error events
+    pub distance: usize,
+    /// The number of error events, A_d
+    pub paths: u64,
+    /// The total number of nonzero information bits of the error events, B_d
+    pub info_weight: u64,
+}
+
+/// The weight spectrum of a code, starting at the free distance
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    pub dfree: usize,
+    /// One term for every distance from `dfree` on, including distances without error events
+    pub terms: Vec<Term>,
+}
+
+/// Computes the free distance of the code, i.e. the smallest weight of an error event
+pub fn free_distance(gs: &Gens) -> usize {
+    dfree(&Trellis::new(gs))
+}
+
+fn dfree(trellis: &Trellis) -> usize {
+    // Dijkstra on the state diagram, the zero state is reached again at the end
+    let states = trellis.states();
+    let mut dist = vec![usize::max_value(); states];
+    let mut done = vec![false; states];
+    let mut dfree = usize::max_value();
+    for b in trellis.branches(0).skip(1) {
+        if b.to == 0 {
+            dfree = ::std::cmp::min(dfree, b.weight());
+        } else {
+            dist[b.to] = ::std::cmp::min(dist[b.to], b.weight());
+        }
+    }
+    done[0] = true;
+
+    loop {
+        let s = match (0..states)
+            .filter(|&s| !done[s] && dist[s] < dfree)
+            .min_by_key(|&s| dist[s]) {
+            Some(s) => s,
+            None => return dfree,
+        };
+        done[s] = true;
+        for b in trellis.branches(s) {
+            let d = dist[s] + b.weight();
+            if b.to == 0 {
+                dfree = ::std::cmp::min(dfree, d);
+            } else if d < dist[b.to] {
+                dist[b.to] = d;
+            }
+        }
+    }
+}
+
+/// Computes the first `terms` terms of the weight spectrum.
+/// Returns `None` for catastrophic codes, whose spectrum is infinite.
+pub fn spectrum(gs: &Gens, terms: usize) -> Option<Spectrum> {
+    let trellis = Trellis::new(gs);
+    let dfree = dfree(&trellis);
+    let max_weight = dfree + terms - 1;
+    let states = trellis.states();
+    let mut res: Vec<Term> = (dfree..max_weight + 1)
+        .map(|d| {
+            Term {
+                distance: d,
+                paths: 0,
+                info_weight: 0,
+            }
+        })
+        .collect();
+
+    // active[s][w] is the number of paths and their total information weight that left
+    // the zero state and are now in state s with output weight w
+    let mut active = vec![vec![(0u64, 0u64); max_weight + 1]; states];
+    for b in trellis.branches(0).skip(1) {
+        let w = b.weight();
+        if w <= max_weight {
+            active[b.to][w].0 += 1;
+            active[b.to][w].1 += b.input_weight() as u64;
+        }
+    }
+
+    // every cycle that avoids the zero state adds weight unless the code is catastrophic,
+    // so all the paths must have returned or exceeded the weight after this many branches
+    for _ in 0..states * (max_weight + 1) + 1 {
+        // the paths that returned to the zero state are complete error events
+        for w in dfree..max_weight + 1 {
+            res[w - dfree].paths += active[0][w].0;
+            res[w - dfree].info_weight += active[0][w].1;
+        }
+        if active.iter().skip(1).all(|ws| ws.iter().all(|&(n, _)| n == 0)) {
+            return Some(Spectrum {
+                dfree: dfree,
+                terms: res,
+            });
+        }
+
+        let mut next = vec![vec![(0u64, 0u64); max_weight + 1]; states];
+        for b in trellis.all_branches().filter(|b| b.from != 0) {
+            let (bits, cw) = (b.input_weight() as u64, b.weight());
+            if cw > max_weight {
+                continue;
+            }
+            for w in 0..max_weight + 1 - cw {
+                let (n, info) = active[b.from][w];
+                let entry = &mut next[b.to][w + cw];
+                entry.0 += n;
+                entry.1 += info + n * bits;
+            }
+        }
+        active = next;
+    }
+    None
+}
+
+/// The probability that an error event at distance `d` is preferred on a BSC with crossover `p`
+fn pairwise_bsc(d: usize, p: f64) -> f64 {
+    let binomial = |e: usize| -> f64 {
+        (0..e).fold(1f64, |acc, i| acc * (d - i) as f64 / (i + 1) as f64)
+    };
+    let mut res = 0f64;
+    for e in (d / 2 + 1)..(d + 1) {
+        res += binomial(e) * p.powi(e as i32) * (1f64 - p).powi((d - e) as i32);
+    }
+    if d % 2 == 0 {
+        // ties are broken at random
+        res += 0.5f64 * binomial(d / 2) * (p * (1f64 - p)).powi((d / 2) as i32);
+    }
+    res
+}
+
+/// The union bound on the bit error rate of hard-decision maximum-likelihood decoding
+/// on a BSC with crossover probability `p`
+pub fn union_bound_bsc(spectrum: &Spectrum, gs: &Gens, p: f64) -> f64 {
+    spectrum.terms
+        .iter()
+        .map(|t| t.info_weight as f64 * pairwise_bsc(t.distance, p))
+        .sum::<f64>() / gs.k as f64
+}
+
+/// The union bound on the bit error rate of soft-decision maximum-likelihood decoding
+/// of BPSK over an AWGN channel at the given Eb/N0 in dB
+pub fn union_bound_awgn(spectrum: &Spectrum, gs: &Gens, ebn0_db: f64) -> f64 {
+    let rate = gs.k as f64 / gs.n as f64;
+    let ebn0 = 10f64.powf(ebn0_db / 10f64);
+    spectrum.terms
+        .iter()
+        // Q(sqrt(2 d R Eb/N0))
+        .map(|t| t.info_weight as f64 * 0.5f64 * erfc((t.distance as f64 * rate * ebn0).sqrt()))
+        .sum::<f64>() / gs.k as f64
+}
+
+#[test]
+fn test_spectrum() {
+    use super::parse_gs;
+
+    // the K = 3 code (7, 5) has the transfer function D^5 / (1 - 2D)
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    assert_eq!(free_distance(&gs), 5);
+    let spec = spectrum(&gs, 4).unwrap();
+    assert_eq!(spec.dfree, 5);
+    assert_eq!(spec.terms.iter().map(|t| t.paths).collect::<Vec<_>>(), vec![1, 2, 4, 8]);
+    assert_eq!(spec.terms.iter().map(|t| t.info_weight).collect::<Vec<_>>(), vec![1, 4, 12, 32]);
+
+    // the K = 7 NASA code
+    let gs = parse_gs("K=7:171,133").unwrap();
+    let spec = spectrum(&gs, 5).unwrap();
+    assert_eq!(spec.dfree, 10);
+    assert_eq!(spec.terms.iter().map(|t| t.paths).collect::<Vec<_>>(), vec![11, 0, 38, 0, 193]);
+    assert_eq!(spec.terms.iter().map(|t| t.info_weight).collect::<Vec<_>>(),
+               vec![36, 0, 211, 0, 1404]);
+
+    // the recursive systematic version has the same distance
+    let gs = parse_gs("1,101/111").unwrap();
+    assert_eq!(free_distance(&gs), 5);
+
+    // 1 + x divides both generators
+    let gs = Gens::new(vec![vec![1, 1], vec![1, 1]]);
+    assert_eq!(spectrum(&gs, 2), None);
+}
+
+#[test]
+fn test_union_bound() {
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let spec = spectrum(&gs, 10).unwrap();
+    let (low, high) = (union_bound_bsc(&spec, &gs, 0.001), union_bound_bsc(&spec, &gs, 0.01));
+    assert!(low < high && high < 0.01);
+    // at small p the terms of d = 5 and 6 are B_5 * 10 p^3 and B_6 * 10 p^3
+    assert!((low / 5e-8 - 1f64).abs() < 0.05);
+    assert!(union_bound_awgn(&spec, &gs, 6f64) < union_bound_awgn(&spec, &gs, 3f64));
+}
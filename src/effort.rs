@@ -0,0 +1,161 @@
+//! Analysis of the computational effort of the stack algorithm.
+//!
+//! The number of computations needed to decode a frame with a sequential decoder
+//! has a Pareto distribution, P(C > x) ~ x^(-rho). The exponent rho depends on
+//! the code rate and the channel, it is at least 1 (finite mean computation)
+//! as long as the rate is below the cut-off rate R0 of the channel.
+
+use std::f64;
+use rand::Rng;
+use super::{Gens, Limits, DecodeError, encode, create_noise_with, decode_stats_with};
+use metric::Bsc;
+
+/// The result of `pareto`
+#[derive(Clone, Debug)]
+pub struct Pareto {
+    /// The estimated Pareto exponent, if there are enough distinct samples
+    pub exponent: Option<f64>,
+    /// The exponent predicted by the random coding bound of the BSC for the code rate
+    pub theoretical: f64,
+    /// The number of extensions of every frame sorted in ascending order,
+    /// frames that exceeded the limit are counted at the limit
+    pub extensions: Vec<usize>,
+    /// The number of frames that exceeded the computational limit
+    pub overflows: usize,
+}
+
+/// Decodes `frames` random messages of `len` bits sent over a BSC with crossover probability `p`
+/// and estimates the Pareto exponent of the number of extensions per frame.
+/// Set `limits` to bound the time spent on the worst frames,
+/// the messages and the noise are drawn from `rng`.
+pub fn pareto<R: Rng>(gs: &Gens,
+                      p: f64,
+                      frames: usize,
+                      len: usize,
+                      limits: &Limits,
+                      rng: &mut R)
+                      -> Pareto {
+    assert_eq!(len % gs.k, 0);
+    let mut extensions = Vec::with_capacity(frames);
+    let mut overflows = 0;
+    for _ in 0..frames {
+        let xs: Vec<u8> = (0..len).map(|_| rng.gen::<bool>() as u8).collect();
+        let ys = create_noise_with(&encode(&xs, gs), p, rng);
+        match decode_stats_with(&Bsc::new(&ys, p), gs, limits) {
+            Ok((_, stats)) => extensions.push(stats.extensions),
+            Err(DecodeError::Overflow { extensions: c, .. }) => {
+                extensions.push(c);
+                overflows += 1;
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+    extensions.sort();
+
+    Pareto {
+        exponent: estimate_exponent(&extensions),
+        theoretical: theoretical_exponent(gs.k as f64 / gs.n as f64, p),
+        extensions: extensions,
+        overflows: overflows,
+    }
+}
+
+/// Estimates the Pareto exponent of the samples (sorted in ascending order) by a least
+/// squares fit of log P(C >= x) against log x over the upper half of the samples.
+pub fn estimate_exponent(samples: &[usize]) -> Option<f64> {
+    let n = samples.len();
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    for i in n / 2..n {
+        // the empirical P(C >= x) is only taken at the first of equal samples
+        if i > 0 && samples[i - 1] == samples[i] || samples[i] == 0 {
+            continue;
+        }
+        let ccdf = (n - i) as f64 / n as f64;
+        points.push(((samples[i] as f64).ln(), ccdf.ln()));
+    }
+    if points.len() < 2 {
+        return None;
+    }
+
+    let len = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / len;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / len;
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    Some(-sxy / sxx)
+}
+
+/// Gallager's function E0(rho) of the BSC with crossover probability `p` in bits
+pub fn gallager_e0(rho: f64, p: f64) -> f64 {
+    let e = 1f64 / (1f64 + rho);
+    rho - (1f64 + rho) * (p.powf(e) + (1f64 - p).powf(e)).log2()
+}
+
+/// The Pareto exponent rho for rate `r` on the BSC, i.e. the solution of r = E0(rho) / rho.
+/// Returns 0 when the rate is at or above the capacity.
+pub fn theoretical_exponent(r: f64, p: f64) -> f64 {
+    assert!(p > 0f64 && p < 1f64);
+    // E0(rho) / rho decreases from the capacity at rho = 0
+    let f = |rho: f64| gallager_e0(rho, p) / rho - r;
+    if f(1e-9) <= 0f64 {
+        return 0f64;
+    }
+
+    let (mut lo, mut hi) = (1e-9, 1f64);
+    while f(hi) > 0f64 {
+        hi *= 2f64;
+        if hi > 1e6 {
+            return f64::INFINITY;
+        }
+    }
+    for _ in 0..100 {
+        let mid = 0.5f64 * (lo + hi);
+        if f(mid) > 0f64 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5f64 * (lo + hi)
+}
+
+#[test]
+fn test_theoretical_exponent() {
+    let p = 0.05;
+    // at the cut-off rate R0 = E0(1) the exponent is exactly 1
+    let r0 = gallager_e0(1f64, p);
+    assert!((theoretical_exponent(r0, p) - 1f64).abs() < 1e-6);
+    assert!(theoretical_exponent(r0 / 2f64, p) > 1f64);
+    assert!(theoretical_exponent(r0 * 1.1, p) < 1f64);
+    assert_eq!(theoretical_exponent(0.9, p), 0f64);
+}
+
+#[test]
+fn test_estimate_exponent() {
+    // samples of a Pareto distribution with exponent 2, P(C >= x) = x^-2
+    let n = 10000;
+    let mut samples: Vec<usize> = (1..n + 1)
+        .map(|i| (100f64 * (n as f64 / i as f64).sqrt()) as usize)
+        .collect();
+    samples.sort();
+    let rho = estimate_exponent(&samples).unwrap();
+    assert!((rho - 2f64).abs() < 0.1, "rho = {}", rho);
+    assert_eq!(estimate_exponent(&[5, 5, 5, 5]), None);
+}
+
+#[test]
+fn test_pareto() {
+    use super::seeded_rng;
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let limits = Limits {
+        max_extensions: Some(1000),
+        max_stack: None,
+    };
+    let res = pareto(&gs, 0.03, 50, 20, &limits, &mut seeded_rng(13));
+    assert_eq!(res.extensions.len(), 50);
+    // every frame needs at least one extension per branch
+    assert!(res.extensions[0] >= 22);
+    assert!(res.extensions[49] <= 1000);
+    // rate 1/2 is below R0 = 0.576 at p = 0.03
+    assert!(res.theoretical > 1f64);
+}
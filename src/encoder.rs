@@ -0,0 +1,151 @@
+//! A stateful encoder for encoding long or endless streams in pieces.
+
+use super::{Gens, next_state, next_state_word, to_input};
+use bitvec::BitVec;
+
+/// Holds the shift register state so that the input can be fed incrementally,
+/// every complete input symbol (k bits) produces n output bits.
+#[derive(Debug)]
+pub struct Encoder<'a> {
+    gs: &'a Gens,
+    state: usize,
+    /// the bits of an incomplete input symbol
+    pending: Vec<u8>,
+}
+
+impl<'a> Encoder<'a> {
+    /// Create an encoder in the zero state
+    pub fn new(gs: &'a Gens) -> Encoder<'a> {
+        Encoder {
+            gs: gs,
+            state: 0,
+            pending: Vec::with_capacity(gs.k),
+        }
+    }
+
+    /// The current register state, see `set_state` for the layout
+    pub fn state(&self) -> usize {
+        self.state
+    }
+
+    /// Overwrite the register state and drop any incomplete input symbol.
+    /// The registers are stored one after another, where bit `j - 1` of the
+    /// register of input i holds the register input of time t - j.
+    pub fn set_state(&mut self, state: usize) {
+        assert!(state < self.gs.states());
+        self.state = state;
+        self.pending.clear();
+    }
+
+    /// Feed a single bit, returns the n output bits once a complete input symbol is available
+    pub fn push(&mut self, x: u8) -> Vec<u8> {
+        assert!(x == 0 || x == 1);
+        self.pending.push(x);
+        if self.pending.len() < self.gs.k {
+            return Vec::new();
+        }
+
+        let (state, c) = next_state(self.state, to_input(&self.pending), self.gs);
+        self.state = state;
+        self.pending.clear();
+        c
+    }
+
+    /// Feed a chunk of bits of any length, returns the output of all the completed input symbols
+    pub fn push_bits(&mut self, xs: &[u8]) -> Vec<u8> {
+        let mut c = Vec::with_capacity((self.pending.len() + xs.len()) / self.gs.k * self.gs.n);
+        for &x in xs {
+            c.extend(self.push(x));
+        }
+        c
+    }
+
+    /// Same as `push_bits` but on packed bits, the complete input symbols are read as words
+    pub fn push_packed(&mut self, xs: &BitVec) -> BitVec {
+        let k = self.gs.k;
+        let mut c = BitVec::with_capacity((self.pending.len() + xs.len()) / k * self.gs.n);
+        let mut i = 0;
+        while !self.pending.is_empty() && i < xs.len() {
+            c.extend_from_bits(&self.push(xs.get(i)));
+            i += 1;
+        }
+        while i + k <= xs.len() {
+            let (state, cs) = next_state_word(self.state, xs.word(i, k) as usize, self.gs);
+            self.state = state;
+            c.push_word(cs, self.gs.n);
+            i += k;
+        }
+        for j in i..xs.len() {
+            self.push(xs.get(j));
+        }
+        c
+    }
+
+    /// Feed the m tail inputs that bring the encoder back to the zero state,
+    /// returns their output. Incomplete input symbols are not allowed.
+    pub fn flush(&mut self) -> Vec<u8> {
+        assert!(self.pending.is_empty(), "Cannot flush an incomplete input symbol");
+        let mut c = Vec::with_capacity(self.gs.m * self.gs.n);
+        for _ in 0..self.gs.m {
+            let (state, cs) = next_state(self.state, self.gs.tail_input(self.state), self.gs);
+            self.state = state;
+            c.extend(cs);
+        }
+        c
+    }
+
+    /// Same as `flush` but appends the output to the packed bits `c`
+    pub fn flush_packed(&mut self, c: &mut BitVec) {
+        assert!(self.pending.is_empty(), "Cannot flush an incomplete input symbol");
+        for _ in 0..self.gs.m {
+            let (state, cs) = next_state_word(self.state, self.gs.tail_input(self.state), self.gs);
+            self.state = state;
+            c.push_word(cs, self.gs.n);
+        }
+    }
+}
+
+#[test]
+fn test_encoder_stream() {
+    use super::{encode, encode_};
+
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let xs = vec![1, 0, 1, 1, 0, 0, 1, 1, 1];
+
+    let mut enc = Encoder::new(&gs);
+    let mut ys = Vec::new();
+    for chunk in xs.chunks(4) {
+        ys.extend(enc.push_bits(chunk));
+    }
+    assert_eq!(ys, encode_(&xs, &gs));
+    assert_eq!(enc.state(), 3);
+
+    ys.extend(enc.flush());
+    assert_eq!(enc.state(), 0);
+    assert_eq!(ys, encode(&xs, &gs));
+
+    // continuing from a saved state is the same as encoding in one go
+    enc.set_state(3);
+    assert_eq!(enc.push_bits(&[0, 1]), encode_(&[1, 1, 0, 1].to_vec(), &gs)[6..].to_vec());
+}
+
+#[test]
+fn test_encoder_partial_symbols() {
+    let gs = Gens::with_inputs(vec![vec![vec![1, 1], vec![0, 1], vec![1, 1]],
+                                    vec![vec![0, 1], vec![1, 0], vec![1, 0]]]);
+    let mut enc = Encoder::new(&gs);
+    assert!(enc.push(1).is_empty());
+    assert_eq!(enc.push(0).len(), gs.n);
+    assert_eq!(enc.push_bits(&[1, 1, 0]).len(), gs.n);
+    enc.set_state(0);
+    assert_eq!(enc.push_bits(&[1]).len(), 0);
+
+    // packed input may also end or start in the middle of a symbol
+    let xs = vec![1, 0, 1, 1, 0, 1, 1];
+    enc.set_state(0);
+    let ys = enc.push_bits(&xs);
+    enc.set_state(0);
+    let mut packed = enc.push_packed(&BitVec::from_bits(&xs[..3]));
+    packed.extend_from_bits(&enc.push_packed(&BitVec::from_bits(&xs[3..])).to_bits());
+    assert_eq!(packed.to_bits(), ys);
+}
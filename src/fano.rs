@@ -0,0 +1,190 @@
+//! Sequential decoding using the Fano algorithm.
+//!
+//! Unlike the stack algorithm the Fano algorithm only keeps the current path,
+//! it moves forward and backward through the tree guided by a running threshold
+//! which is raised or lowered in steps of `delta`.
+
+use std::f64;
+use super::{Gens, DecodeError, next_state_word, branch_inputs, branch_metric};
+use bitvec::BitVec;
+use metric::{Metric, Bsc};
+
+/// The number of moves made by the Fano algorithm
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FanoStats {
+    pub forward: usize,
+    pub backward: usize,
+}
+
+/// Returns the children (input symbol, next state, path metric) of the node in `state` at
+/// depth `idx`, sorted from the best to the worst. The last m branches only have one child.
+fn children<M: Metric>(state: usize, idx: usize, mu: f64, l: usize, metric: &M, gs: &Gens)
+                       -> Vec<(usize, usize, f64)> {
+    let us = branch_inputs(state, idx >= l, gs);
+    let mut res = Vec::with_capacity(us.len());
+    for u in us {
+        let (next, cs) = next_state_word(state, u, gs);
+        res.push((u, next, mu + branch_metric(cs, idx, metric, gs)));
+    }
+
+    // stable sort so that ties favour 0
+    res.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+    res
+}
+
+/// Same as `decode` but returns a tuple of the result and the number of moves
+pub fn decode_(obs: &Vec<u8>, gs: &Gens, p: f64, delta: f64) -> (Vec<u8>, FanoStats) {
+    decode_with(&Bsc::new(obs, p), gs, delta)
+}
+
+/// Same as `decode_` but the channel is described by an arbitrary metric
+pub fn decode_with<M: Metric>(metric: &M, gs: &Gens, delta: f64) -> (Vec<u8>, FanoStats) {
+    match decode_limited_with(metric, gs, delta, None) {
+        Ok(res) => res,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Same as `decode_with` but gives up after `max_forward` forward moves, `None` means unlimited.
+/// The Fano algorithm only keeps a single path, so the stack of an overflow is always 1.
+pub fn decode_limited_with<M: Metric>(metric: &M,
+                                      gs: &Gens,
+                                      delta: f64,
+                                      max_forward: Option<usize>)
+                                      -> Result<(Vec<u8>, FanoStats), DecodeError> {
+    assert!(delta > 0f64);
+    if metric.len() % gs.n != 0 || metric.len() / gs.n < gs.m {
+        return Err(DecodeError::Malformed(format!("received length {} does not fit n = {} \
+                                                   and the tail of {} branches",
+                                                  metric.len(),
+                                                  gs.n,
+                                                  gs.m)));
+    }
+
+    let l = metric.len() / gs.n - gs.m;
+    let mut stats = FanoStats::default();
+
+    // the current path, the metric and encoder state of every node on it (starting with
+    // the root) and the rank of the child that was taken at every depth
+    let mut path = BitVec::with_capacity((l + gs.m) * gs.k);
+    let mut mus = vec![0f64];
+    let mut states = vec![0];
+    let mut ranks: Vec<usize> = Vec::new();
+
+    let mut t = 0f64;
+    let mut rank = 0;
+    let mut depth = 0;
+    while path.len() < (l + gs.m) * gs.k {
+        if max_forward.map_or(false, |max| stats.forward >= max) {
+            return Err(DecodeError::Overflow {
+                extensions: stats.forward,
+                stack: 1,
+                depth: depth,
+                length: l + gs.m,
+            });
+        }
+
+        let mu_b = *mus.last().unwrap();
+        let idx = path.len() / gs.k;
+        let (u, next, mu_f) = children(*states.last().unwrap(), idx, mu_b, l, metric, gs)[rank];
+
+        if mu_f >= t {
+            // move forward and tighten the threshold on the first visit
+            path.push_word(u as u64, gs.k);
+            mus.push(mu_f);
+            states.push(next);
+            ranks.push(rank);
+            stats.forward += 1;
+            depth = ::std::cmp::max(depth, path.len() / gs.k);
+            if mu_b < t + delta {
+                while mu_f >= t + delta {
+                    t += delta;
+                }
+            }
+            rank = 0;
+            continue;
+        }
+
+        // look back until we find an unexplored sibling or must loosen the threshold
+        loop {
+            let d = path.len() / gs.k;
+            let mu_prev = if d == 0 { f64::NEG_INFINITY } else { mus[d - 1] };
+            if mu_prev < t {
+                t -= delta;
+                rank = 0;
+                break;
+            }
+
+            path.truncate((d - 1) * gs.k);
+            mus.pop();
+            states.pop();
+            let r = ranks.pop().unwrap();
+            stats.backward += 1;
+
+            let siblings = if d - 1 < l { gs.inputs() } else { 1 };
+            if r + 1 < siblings {
+                rank = r + 1;
+                break;
+            }
+        }
+    }
+
+    path.truncate(l * gs.k);
+    Ok((path.to_bits(), stats))
+}
+
+/// Perform decoding using the Fano algorithm with threshold step `delta`
+pub fn decode(obs: &Vec<u8>, gs: &Gens, p: f64, delta: f64) -> Vec<u8> {
+    decode_(obs, gs, p, delta).0
+}
+
+#[test]
+fn test_fano_and_stack() {
+    use super::decode;
+
+    let obs = vec![0, 0, 1, 0, 0, 1, 0, 1, 1, 1, 0, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let p = 1f64 / 16f64;
+    let (xs, stats) = decode_(&obs, &gs, p, 1.0);
+    assert_eq!(xs, decode(&obs, &gs, p));
+    assert!(stats.forward >= obs.len() / gs.n);
+    assert_eq!(stats.forward - stats.backward, obs.len() / gs.n);
+}
+
+#[test]
+fn test_fano_errors() {
+    use super::encode;
+
+    let xs = vec![1, 1, 0, 1, 0, 0, 1, 0, 1, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let mut ys = encode(&xs, &gs);
+    assert_eq!(decode_(&ys, &gs, 0.1, 2.0), (xs.clone(), FanoStats { forward: 12, backward: 0 }));
+
+    ys[1] ^= 1;
+    ys[14] ^= 1;
+    for &delta in &[0.5, 1.0, 2.0, 4.0] {
+        assert_eq!(decode(&ys, &gs, 0.1, delta), xs);
+    }
+}
+
+#[test]
+fn test_fano_limited() {
+    use super::encode;
+
+    let xs = vec![1, 1, 0, 1, 0, 0, 1, 0, 1, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let mut ys = encode(&xs, &gs);
+    ys[1] ^= 1;
+    ys[2] ^= 1;
+    let (zs, stats) = decode_(&ys, &gs, 0.1, 1.0);
+    let limited = decode_limited_with(&Bsc::new(&ys, 0.1), &gs, 1.0, Some(stats.forward));
+    assert_eq!(limited, Ok((zs, stats)));
+
+    match decode_limited_with(&Bsc::new(&ys, 0.1), &gs, 1.0, Some(stats.forward - 1)) {
+        Err(DecodeError::Overflow { extensions, stack, length, .. }) => {
+            assert_eq!((extensions, stack, length), (stats.forward - 1, 1, 12));
+        }
+        res => panic!("expected an overflow, got {:?}", res),
+    }
+    assert!(decode_limited_with(&Bsc::new(&ys[1..], 0.1), &gs, 1.0, None).is_err());
+}
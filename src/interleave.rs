@@ -0,0 +1,198 @@
+//! Interleavers for channels with burst errors.
+//!
+//! The code is interleaved after `encode` and deinterleaved after the channel,
+//! so a burst on the channel turns into scattered errors that the decoders can correct.
+//! The received symbols can be deinterleaved directly, or the metric of the received
+//! symbols can be wrapped with `Interleaver::deinterleave_metric`.
+
+use rand::Rng;
+use metric::Metric;
+
+/// A mapping of the symbols of a frame to positions in the interleaved frame
+pub trait Interleaver {
+    /// The position in the interleaved frame of symbol `i` of a frame of `len` symbols
+    fn position(&self, i: usize, len: usize) -> usize;
+
+    /// The number of extra symbols the interleaver adds to a frame
+    fn delay(&self) -> usize {
+        0
+    }
+
+    /// Interleaves the frame `xs`, the positions that carry no symbol are filled with
+    /// `T::default()`
+    fn interleave<T: Clone + Default>(&self, xs: &[T]) -> Vec<T> {
+        let mut ys = vec![T::default(); xs.len() + self.delay()];
+        for (i, x) in xs.iter().enumerate() {
+            ys[self.position(i, xs.len())] = x.clone();
+        }
+        ys
+    }
+
+    /// Restores the original order of the interleaved frame `ys`
+    fn deinterleave<T: Clone>(&self, ys: &[T]) -> Vec<T> {
+        assert!(ys.len() >= self.delay());
+        let len = ys.len() - self.delay();
+        (0..len).map(|i| ys[self.position(i, len)].clone()).collect()
+    }
+
+    /// Wraps the metric of the received interleaved frame so that it can be used
+    /// with the code in its original order
+    fn deinterleave_metric<'a, M: Metric>(&self, metric: &'a M) -> Deinterleaved<'a, M> {
+        assert!(metric.len() >= self.delay());
+        let len = metric.len() - self.delay();
+        Deinterleaved {
+            inner: metric,
+            map: (0..len).map(|i| self.position(i, len)).collect(),
+        }
+    }
+}
+
+/// The metric of the code given the metric of the interleaved code
+pub struct Deinterleaved<'a, M: Metric + 'a> {
+    inner: &'a M,
+    /// the position in the interleaved frame of every symbol
+    map: Vec<usize>,
+}
+
+impl<'a, M: Metric> Metric for Deinterleaved<'a, M> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        self.inner.symbol(self.map[i], x)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+}
+
+/// Row-column block interleaver, every block is written row by row and read column by column
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Block {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Block {
+    /// The frame length must be a multiple of `rows * cols`
+    pub fn new(rows: usize, cols: usize) -> Block {
+        assert!(rows > 0 && cols > 0);
+        Block {
+            rows: rows,
+            cols: cols,
+        }
+    }
+}
+
+impl Interleaver for Block {
+    fn position(&self, i: usize, len: usize) -> usize {
+        let size = self.rows * self.cols;
+        assert_eq!(len % size, 0);
+        let (b, r, c) = (i / size, i % size / self.cols, i % self.cols);
+        b * size + c * self.rows + r
+    }
+}
+
+/// Convolutional (Forney) interleaver with `branches` branches, where branch j delays its
+/// symbols by j * `depth` symbols of that branch. The frame grows by the largest delay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Convolutional {
+    pub branches: usize,
+    pub depth: usize,
+}
+
+impl Convolutional {
+    pub fn new(branches: usize, depth: usize) -> Convolutional {
+        assert!(branches > 0);
+        Convolutional {
+            branches: branches,
+            depth: depth,
+        }
+    }
+}
+
+impl Interleaver for Convolutional {
+    fn position(&self, i: usize, _len: usize) -> usize {
+        // the symbols on branch j are delayed by j * depth commutator cycles
+        i + (i % self.branches) * self.depth * self.branches
+    }
+
+    fn delay(&self) -> usize {
+        (self.branches - 1) * self.depth * self.branches
+    }
+}
+
+/// Pseudo-random interleaver of a fixed frame length
+#[derive(Clone, Debug, PartialEq)]
+pub struct Random {
+    /// `perm[i]` is the position of symbol i in the interleaved frame
+    pub perm: Vec<usize>,
+}
+
+impl Random {
+    /// A random permutation of `len` symbols drawn from `rng`
+    pub fn new<R: Rng>(len: usize, rng: &mut R) -> Random {
+        let mut perm: Vec<usize> = (0..len).collect();
+        rng.shuffle(&mut perm);
+        Random { perm: perm }
+    }
+}
+
+impl Interleaver for Random {
+    fn position(&self, i: usize, len: usize) -> usize {
+        assert_eq!(len, self.perm.len());
+        self.perm[i]
+    }
+}
+
+#[test]
+fn test_interleavers() {
+    use super::seeded_rng;
+    let xs: Vec<u8> = (0..12).collect();
+
+    let block = Block::new(3, 4);
+    assert_eq!(block.interleave(&xs), vec![0, 4, 8, 1, 5, 9, 2, 6, 10, 3, 7, 11]);
+    assert_eq!(block.deinterleave(&block.interleave(&xs)), xs);
+
+    let conv = Convolutional::new(3, 1);
+    let ys = conv.interleave(&xs);
+    assert_eq!(ys.len(), 18);
+    // branch 1 is delayed by one cycle and branch 2 by two, the gaps are filled with 0
+    assert_eq!(&ys[..9], &[0, 0, 0, 3, 1, 0, 6, 4, 2]);
+    assert_eq!(conv.deinterleave(&ys), xs);
+
+    let random = Random::new(12, &mut seeded_rng(16));
+    let ys = random.interleave(&xs);
+    assert!(ys != xs);
+    assert_eq!(random.deinterleave(&ys), xs);
+}
+
+#[test]
+fn test_interleaved_burst() {
+    use super::{Gens, encode, decode, decode_with};
+    use metric::Bsc;
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let xs = vec![1, 0, 1, 1, 0, 0, 1, 0, 1, 1];
+    let p = 0.1;
+    let code = encode(&xs, &gs);
+    let burst = |ys: &mut Vec<u8>| {
+        for y in ys[9..15].iter_mut() {
+            *y = 1 - *y;
+        }
+    };
+
+    // a burst over two branches cannot be corrected
+    let mut ys = code.clone();
+    burst(&mut ys);
+    assert!(decode(&ys, &gs, p) != xs);
+
+    // but the same burst is spread out by the interleaver
+    let block = Block::new(6, 6);
+    let mut ys = block.interleave(&code);
+    burst(&mut ys);
+    assert_eq!(decode(&block.deinterleave(&ys), &gs, p), xs);
+    assert_eq!(decode_with(&block.deinterleave_metric(&Bsc::new(&ys, p)), &gs).0, xs);
+
+    let conv = Convolutional::new(6, 1);
+    let mut ys = conv.interleave(&code);
+    burst(&mut ys);
+    assert_eq!(decode(&conv.deinterleave(&ys), &gs, p), xs);
+}
@@ -6,7 +6,33 @@ extern crate rand;
 use std::io::{Error, ErrorKind};
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
-use rand::random;
+use std::fmt;
+use std::error;
+use rand::{random, thread_rng, Rng, SeedableRng, XorShiftRng};
+use metric::{Metric, Bsc, PackedBsc, Llr, Quantized};
+use encoder::Encoder;
+use bitvec::BitVec;
+
+pub mod metric;
+pub mod channel;
+pub mod interleave;
+pub mod bitvec;
+pub mod encoder;
+pub mod trellis;
+pub mod fano;
+pub mod bcjr;
+pub mod puncture;
+pub mod tailbiting;
+pub mod turbo;
+pub mod sim;
+pub mod distance;
+pub mod catastrophic;
+pub mod profile;
+pub mod search;
+pub mod catalogue;
+pub mod stream;
+pub mod effort;
+pub mod viterbi;
 
 // parse and check the binary input
 pub fn parse_bin(xs: &str) -> Result<Vec<u8>, Error> {
@@ -22,20 +48,325 @@ pub fn parse_bin(xs: &str) -> Result<Vec<u8>, Error> {
     Ok(res)
 }
 
+/// Parse the generators, generators of the same input are separated by commas
+/// and the inputs of a rate k/n code are separated by semicolons.
+///
+/// A generator is written in one of the following notations:
+///
+/// * binary coefficients starting from x^0, e.g. `1011`,
+/// * octal with the `0o` prefix, e.g. `0o13`, where the constraint length is the longest
+///   generator of the input and the first (most significant) bit is the coefficient of x^0,
+/// * octal after a constraint length prefix for the input, e.g. `K=7:171,133`,
+/// * a polynomial expression, e.g. `1 + x^2 + x^3`.
+///
+/// A generator written as `1` is always the constant polynomial 1.
+///
+/// Recursive encoders are written with the feedback polynomial as the denominator,
+/// e.g. `1,101/111` is the systematic encoder (1, 5/7).
+///
+/// Catastrophic codes and generators that are all zeros or start with 0 are rejected,
+/// see the `catastrophic` module.
 pub fn parse_gs(gs: &str) -> Result<Gens, Error> {
+    let mut rows = Vec::new();
+    let mut fbs = Vec::new();
+    for row in gs.split(';') {
+        let (gs, fb) = try!(parse_gs_row(row));
+        rows.push(gs);
+        fbs.push(fb);
+    }
+
+    if rows.iter().any(|row| row.len() != rows[0].len()) {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              "Every input must have the same number of generators"));
+    }
+
+    if rows.len() > rows[0].len() {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              format!("The number of inputs {} is larger than the number of \
+                                       outputs {}",
+                                      rows.len(),
+                                      rows[0].len())));
+    }
+
+    if rows[0].len() > MAX_OUTPUTS {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              format!("At most {} outputs are supported", MAX_OUTPUTS)));
+    }
+
+    if rows.len() > 1 && fbs.iter().any(|fb| fb.is_some()) {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              "Feedback is only supported for codes with a single input"));
+    }
+
+    let memory: usize = rows.iter().map(|row| row[0].len() - 1).sum();
+    if memory > MAX_MEMORY {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              format!("The total memory {} is larger than the maximum of {}",
+                                      memory,
+                                      MAX_MEMORY)));
+    }
+
+    let gs = match fbs.pop().unwrap() {
+        Some(fb) => Gens::recursive(fb, rows.pop().unwrap()),
+        None => Gens::with_inputs(rows),
+    };
+    try!(check_gs(&gs));
+    Ok(gs)
+}
+
+/// Rejects generators that are all zeros, delayed generators and catastrophic codes.
+/// Single generators of rate k/n codes may be zero or delayed as long as every output
+/// has a nonzero generator and every input has a generator that starts with 1.
+fn check_gs(gs: &Gens) -> Result<(), Error> {
+    let invalid = |msg: String| Err(Error::new(ErrorKind::InvalidInput, msg));
+    let is_zero = |g: &Vec<u8>| g.iter().all(|&c| c == 0);
+    if gs.k == 1 {
+        for (j, g) in gs.gs.iter().enumerate() {
+            if is_zero(g) {
+                return invalid(format!("Generator {} is all zeros", j + 1));
+            }
+            if g[0] == 0 {
+                return invalid(format!("Generator {} has a leading coefficient of 0", j + 1));
+            }
+        }
+    } else {
+        for j in 0..gs.n {
+            if (0..gs.k).all(|i| is_zero(gs.gen(i, j))) {
+                return invalid(format!("Output {} has only all-zero generators", j + 1));
+            }
+        }
+        for i in 0..gs.k {
+            if (0..gs.n).all(|j| gs.gen(i, j)[0] == 0) {
+                return invalid(format!("Input {} has no generator with a leading coefficient of 1",
+                                       i + 1));
+            }
+        }
+    }
+
+    let factor = catastrophic::common_factor(gs);
+    if factor.is_empty() {
+        return invalid("The generator matrix has rank below k, the code cannot be inverted"
+            .to_string());
+    }
+    if factor != vec![1] {
+        return invalid(format!("Catastrophic code, the generators have the common factor {}",
+                               catastrophic::format_poly(&factor)));
+    }
+    Ok(())
+}
+
+/// A generator before the constraint length of its input is known
+enum GenTerm {
+    Coeffs(Vec<u8>),
+    Octal(u64),
+}
+
+impl GenTerm {
+    /// The number of coefficients needed to represent the generator
+    fn len(&self) -> usize {
+        match *self {
+            GenTerm::Coeffs(ref g) => g.len(),
+            GenTerm::Octal(g) => 64 - g.leading_zeros() as usize,
+        }
+    }
+
+    /// Convert the generator to coefficients, octal generators are right aligned to `k` bits
+    fn into_coeffs(self, k: usize) -> Vec<u8> {
+        match self {
+            GenTerm::Coeffs(g) => g,
+            GenTerm::Octal(g) => (0..k).map(|i| ((g >> (k - 1 - i)) & 1) as u8).collect(),
+        }
+    }
+}
+
+fn invalid_gen(msg: &str, g: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("{} in generator \"{}\"", msg, g.trim()))
+}
+
+/// Parse a single generator, `octal` is set when the input has a constraint length prefix
+fn parse_gen(g: &str, octal: bool) -> Result<GenTerm, Error> {
+    let t = g.trim();
+    if t.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "Empty generator"));
+    }
+
+    // a plain 1 is always the identity, e.g. the systematic output in K=3:1,5/7
+    if t == "1" || t.contains(|c| c == '+' || c == 'x' || c == 'D') {
+        return parse_gen_poly(t).map(GenTerm::Coeffs);
+    }
+
+    let (digits, radix) = if t.starts_with("0o") {
+        (&t[2..], 8)
+    } else if octal {
+        (t, 8)
+    } else {
+        (t, 2)
+    };
+
+    if radix == 2 {
+        let mut res = Vec::new();
+        for c in digits.chars() {
+            match c {
+                '0' => res.push(0),
+                '1' => res.push(1),
+                ' ' => (),
+                _ => return Err(invalid_gen(&format!("Invalid binary digit '{}'", c), g)),
+            }
+        }
+        return Ok(GenTerm::Coeffs(res));
+    }
+
+    let mut res: u64 = 0;
+    for c in digits.chars() {
+        let d = match c.to_digit(8) {
+            Some(d) => d as u64,
+            None => return Err(invalid_gen(&format!("Invalid octal digit '{}'", c), g)),
+        };
+        if res.leading_zeros() < 3 {
+            return Err(invalid_gen("Octal value too large", g));
+        }
+        res = (res << 3) | d;
+    }
+    if digits.is_empty() {
+        return Err(invalid_gen("Missing octal digits", g));
+    }
+    Ok(GenTerm::Octal(res))
+}
+
+/// Parse a polynomial expression such as `1 + x^2 + x^3`, `D` may be used instead of `x`
+fn parse_gen_poly(g: &str) -> Result<Vec<u8>, Error> {
     let mut res = Vec::new();
+    for term in g.split('+') {
+        let term = term.trim();
+        let power = if term == "0" {
+            continue;
+        } else if term == "1" {
+            0
+        } else if term == "x" || term == "D" {
+            1
+        } else if term.starts_with("x^") || term.starts_with("D^") {
+            match term[2..].trim().parse::<usize>() {
+                Ok(p) => p,
+                Err(_) => return Err(invalid_gen(&format!("Invalid exponent in term '{}'", term), g)),
+            }
+        } else if term.is_empty() {
+            return Err(invalid_gen("Empty term", g));
+        } else {
+            return Err(invalid_gen(&format!("Invalid term '{}'", term), g));
+        };
+        if power > MAX_MEMORY {
+            return Err(invalid_gen(&format!("Exponent too large in term '{}'", term), g));
+        }
+
+        if power >= res.len() {
+            res.resize(power + 1, 0);
+        }
+        if res[power] == 1 {
+            return Err(invalid_gen(&format!("Repeated term '{}'", term), g));
+        }
+        res[power] = 1;
+    }
+    Ok(res)
+}
+
+/// Parse the generators of a single input, returns the generators and the feedback polynomial
+fn parse_gs_row(gs: &str) -> Result<(Vec<Vec<u8>>, Option<Vec<u8>>), Error> {
+    // the optional constraint length prefix, i.e. K=7:
+    let (k, gs) = match gs.find(':') {
+        Some(i) => {
+            let prefix = gs[..i].trim();
+            if !prefix.starts_with("K=") {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                      format!("Invalid prefix \"{}\", expected K=<constraint length>",
+                                              prefix)));
+            }
+            match prefix[2..].trim().parse::<usize>() {
+                Ok(k) if k > MAX_MEMORY + 1 => {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                                          format!("Constraint length {} is larger than the \
+                                                   maximum of {}",
+                                                  k,
+                                                  MAX_MEMORY + 1)))
+                }
+                Ok(k) if k > 0 => (Some(k), &gs[i + 1..]),
+                _ => {
+                    return Err(Error::new(ErrorKind::InvalidInput,
+                                          format!("Invalid constraint length in \"{}\"", prefix)))
+                }
+            }
+        }
+        None => (None, gs),
+    };
+
+    let mut terms = Vec::new();
     for g in gs.split(',') {
-        res.push(try!(parse_bin(g)));
+        let mut parts = g.split('/');
+        let num = try!(parse_gen(parts.next().unwrap(), k.is_some()));
+        let den = match parts.next() {
+            Some(den) => Some(try!(parse_gen(den, k.is_some()))),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(invalid_gen("Too many '/'", g));
+        }
+        terms.push((g, num, den));
     }
 
+    // the constraint length used to align octal generators
+    let max_len = terms.iter()
+        .flat_map(|&(_, ref num, ref den)| Some(num).into_iter().chain(den.iter()))
+        .map(|t| t.len())
+        .max()
+        .unwrap();
+    let k = match k {
+        Some(k) if max_len > k => {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  format!("Generators are longer than the constraint length {}", k)))
+        }
+        Some(k) => k,
+        None => max_len,
+    };
+
+    let mut res = Vec::new();
+    let mut fb: Option<Vec<u8>> = None;
+    for (g, num, den) in terms {
+        let num = num.into_coeffs(k);
+        let has_den = match den {
+            Some(den) => {
+                let mut den = den.into_coeffs(k);
+                while den.last() == Some(&0) {
+                    den.pop();
+                }
+                if den.first() != Some(&1) {
+                    return Err(invalid_gen("Feedback polynomial must start with 1", g));
+                }
+                if fb.is_some() && fb.as_ref() != Some(&den) {
+                    return Err(invalid_gen("Different feedback polynomial", g));
+                }
+                fb = Some(den);
+                true
+            }
+            None => false,
+        };
+        res.push((num, has_den));
+    }
+
+    // generators without a denominator are brought to the common denominator
+    let mut res: Vec<Vec<u8>> = res.into_iter()
+        .map(|(g, has_den)| match fb {
+            Some(ref f) if !has_den => poly_mul(&g, f),
+            _ => g,
+        })
+        .collect();
+
     if res.len() <= 0 {
         return Err(Error::new(ErrorKind::InvalidInput, "No generators"));
     }
 
-    // get the maximum length out of all the generators, consider using .map?
+    // get the maximum length out of all the generators and the feedback, consider using .map?
     let max_len = {
         let mut tmp_len = 0;
-        for g in &res {
+        for g in res.iter().chain(fb.iter()) {
             if g.len() > tmp_len {
                 tmp_len = g.len();
             }
@@ -48,13 +379,24 @@ pub fn parse_gs(gs: &str) -> Result<Gens, Error> {
     }
 
     // pad short generators with zeros
-    for mut g in &mut res {
+    for g in res.iter_mut().chain(fb.iter_mut()) {
         for _ in 0..max_len - g.len() {
             g.push(0);
         }
     }
 
-    Ok(Gens::new(res))
+    Ok((res, fb))
+}
+
+/// Multiplies two polynomials over GF(2)
+fn poly_mul(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut c = vec![0; (a.len() + b.len()).saturating_sub(1)];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            c[i + j] ^= x * y;
+        }
+    }
+    c
 }
 
 pub fn parse_pr(pr: &str) -> Result<f64, Error> {
@@ -69,22 +411,108 @@ pub fn parse_pr(pr: &str) -> Result<f64, Error> {
     Ok(p)
 }
 
+/// The largest total memory of an encoder, the registers of all the inputs are stored in the
+/// bits of a `usize` state
+pub const MAX_MEMORY: usize = ::std::mem::size_of::<usize>() * 8 - 1;
+
+/// The largest number of outputs, the output bits of a branch are computed in a single word
+pub const MAX_OUTPUTS: usize = 64;
+
+/// The generators of a rate k/n convolutional code
 #[derive(Debug)]
 pub struct Gens {
+    /// `gs[i * n + j]` is the generator from input i to output j
     pub gs: Vec<Vec<u8>>,
+    /// The largest memory out of all the inputs
     pub m: usize,
     pub n: usize,
+    pub k: usize,
+    /// The memory of every input
+    pub ms: Vec<usize>,
+    /// The feedback polynomial of a recursive encoder
+    pub fb: Option<Vec<u8>>,
+    /// The generators as words in the order of `gs`, bit l is the coefficient of x^l
+    masks: Vec<u64>,
+    /// The feedback coefficients of x^1 and above as a word, bit l - 1 is the coefficient of x^l
+    fb_taps: u64,
 }
 
 impl Gens {
+    /// Create a rate 1/n code
     pub fn new(gs: Vec<Vec<u8>>) -> Gens {
-        // we expect gs to be in the correct format (equal lengths and binary)
+        Gens::with_inputs(vec![gs])
+    }
+
+    /// Create a rate k/n code, `gs[i][j]` is the generator from input i to output j
+    pub fn with_inputs(gs: Vec<Vec<Vec<u8>>>) -> Gens {
+        // we expect gs to be in the correct format (equal lengths per input)
+        assert!(gs.iter().flat_map(|row| row.iter()).all(|g| is_binary(g)),
+                "Generator coefficients must be 0 or 1");
+        let ms: Vec<usize> = gs.iter().map(|row| row[0].len() - 1).collect();
+        assert!(ms.iter().sum::<usize>() <= MAX_MEMORY,
+                "The total memory must be at most {}",
+                MAX_MEMORY);
+        assert!(gs[0].len() <= MAX_OUTPUTS,
+                "The number of outputs must be at most {}",
+                MAX_OUTPUTS);
+        let gs: Vec<Vec<u8>> = gs.into_iter().flat_map(|row| row.into_iter()).collect();
         Gens {
-            m: gs[0].len() - 1,
-            n: gs.len(),
+            m: *ms.iter().max().unwrap(),
+            n: gs.len() / ms.len(),
+            k: ms.len(),
+            ms: ms,
+            masks: gs.iter().map(|g| to_mask(g)).collect(),
             gs: gs,
+            fb: None,
+            fb_taps: 0,
         }
     }
+
+    /// Create a rate 1/n recursive code with the feedback polynomial `fb`,
+    /// output j is the polynomial `gs[j]` divided by `fb` applied to the input.
+    pub fn recursive(fb: Vec<u8>, gs: Vec<Vec<u8>>) -> Gens {
+        // we expect fb and gs to have equal lengths
+        assert_eq!(fb[0], 1);
+        assert!(is_binary(&fb), "Feedback coefficients must be 0 or 1");
+        let mut res = Gens::new(gs);
+        res.fb_taps = to_mask(&fb) >> 1;
+        res.fb = Some(fb);
+        res
+    }
+
+    /// Create a recursive systematic code, the first output is the input itself
+    /// and the others are the `parity` polynomials divided by `fb`.
+    pub fn systematic(fb: Vec<u8>, parity: Vec<Vec<u8>>) -> Gens {
+        let mut gs = vec![fb.clone()];
+        gs.extend(parity);
+        Gens::recursive(fb, gs)
+    }
+
+    /// The input symbol of the terminating tail, which is 0 unless the encoder is recursive,
+    /// in which case it cancels the feedback and shifts a 0 into the register.
+    pub fn tail_input(&self, state: usize) -> usize {
+        parity(state as u64 & self.fb_taps) as usize
+    }
+
+    /// The generator from input i to output j
+    pub fn gen(&self, i: usize, j: usize) -> &Vec<u8> {
+        &self.gs[i * self.n + j]
+    }
+
+    /// The total memory of the encoder, i.e. the number of bits in its state
+    pub fn memory(&self) -> usize {
+        self.ms.iter().sum()
+    }
+
+    /// The number of encoder states
+    pub fn states(&self) -> usize {
+        1 << self.memory()
+    }
+
+    /// The number of input symbols, i.e. the number of branches leaving every node
+    pub fn inputs(&self) -> usize {
+        1 << self.k
+    }
 }
 
 /// For tracking the decoding progress and some key data
@@ -99,74 +527,275 @@ pub struct StackResults {
     pub paths: Vec<CodePath>,
 }
 
-// not the most efficient way encoding since the returned value must be drained
-// but we need this function to perform decoding
-fn encode_step(xs: &Vec<u8>, gs: &Gens, i: usize) -> Vec<u8> {
-    let mut c: Vec<u8> = Vec::with_capacity(gs.n);
-    for g in &gs.gs {
-        let mut sum = 0;
-        for (j, coeff) in g.iter().enumerate() {
-            assert!(coeff == &0 || coeff == &1);
-            sum ^= coeff * getx(&xs, i, j);
-        }
-        c.push(sum);
-    }
+/// Same as `encode`, but without pre-processing
+pub fn encode_(xs: &Vec<u8>, gs: &Gens) -> Vec<u8> {
+    encode_from(0, xs, gs).1
+}
+
+/// Encodes `xs` starting from `state`, returns the final state and the code
+fn encode_from(state: usize, xs: &[u8], gs: &Gens) -> (usize, Vec<u8>) {
+    assert_eq!(xs.len() % gs.k, 0);
+    let mut enc = Encoder::new(gs);
+    enc.set_state(state);
+    let c = enc.push_bits(xs);
+    (enc.state(), c)
+}
+
+/// Perform convolutional encoding, the length of `xs` must be a multiple of k
+pub fn encode(xs: &Vec<u8>, gs: &Gens) -> Vec<u8> {
+    // add M tail inputs which bring the encoder back to the zero state
+    assert_eq!(xs.len() % gs.k, 0);
+    let mut enc = Encoder::new(gs);
+    let mut c = enc.push_bits(xs);
+    c.extend(enc.flush());
     c
 }
 
-/// Same as `encode`, but without pre-processing
-pub fn encode_(xs: &Vec<u8>, gs: &Gens) -> Vec<u8> {
-    let mut c: Vec<u8> = Vec::new(); // TODO with_capacity
-    for (i, _) in xs.iter().enumerate() {
-        c.extend_from_slice(&encode_step(xs, gs, i))
-    }
+/// Same as `encode` but on packed bits
+pub fn encode_packed(xs: &BitVec, gs: &Gens) -> BitVec {
+    assert_eq!(xs.len() % gs.k, 0);
+    let mut enc = Encoder::new(gs);
+    let mut c = enc.push_packed(xs);
+    enc.flush_packed(&mut c);
     c
 }
 
-/// Perform convolutional encoding
-pub fn encode(xs: &Vec<u8>, gs: &Gens) -> Vec<u8> {
-    // make a copy and add M number of zeros
-    let mut xs = xs.clone();
-    for _ in 0..gs.m {
-        xs.push(0);
+/// Feeds the input symbol `u` (bit i is the bit of input i) to the encoder in `state`,
+/// returns the next state and the n output bits of the transition.
+/// The registers are stored one after another in `state`, where bit `j - 1` of
+/// the register of input i holds the register input of input i at time t - j.
+/// The register input is the input bit itself unless the encoder has feedback.
+fn next_state(state: usize, u: usize, gs: &Gens) -> (usize, Vec<u8>) {
+    let (next, c) = next_state_word(state, u, gs);
+    (next, (0..gs.n).map(|j| ((c >> j) & 1) as u8).collect())
+}
+
+/// Same as `next_state` but the output bits are returned as a word, bit j is output j.
+/// Every output bit is the parity of the register window masked by the generator.
+fn next_state_word(state: usize, u: usize, gs: &Gens) -> (usize, u64) {
+    assert!(u < gs.inputs());
+    let mut c = 0u64;
+    let mut next = 0;
+    let mut offset = 0;
+    for (i, &m) in gs.ms.iter().enumerate() {
+        let mask = (1 << m) - 1;
+        let reg = (state >> offset) & mask;
+        let x = ((u >> i) & 1) ^ parity(reg as u64 & gs.fb_taps) as usize;
+        // bit l of the window is the register input of time t - l
+        let window = (reg as u64) << 1 | x as u64;
+        for j in 0..gs.n {
+            c ^= parity(window & gs.masks[i * gs.n + j]) << j;
+        }
+        next |= (((reg << 1) | x) & mask) << offset;
+        offset += m;
     }
-    encode_(&xs, gs)
+    (next, c)
 }
 
-/// Returns xs[i - j] when possible otherwise 0 representing the register
-fn getx(xs: &Vec<u8>, i: usize, j: usize) -> u8 {
-    if j > i {
-        return 0;
+fn is_binary(g: &[u8]) -> bool {
+    g.iter().all(|&coeff| coeff == 0 || coeff == 1)
+}
+
+/// The coefficients of a polynomial as a word, bit l is the coefficient of x^l
+fn to_mask(g: &[u8]) -> u64 {
+    g.iter().rev().fold(0, |acc, &c| acc << 1 | c as u64)
+}
+
+/// The sum of the bits of `w` in GF(2)
+fn parity(w: u64) -> u64 {
+    (w.count_ones() & 1) as u64
+}
+
+/// The input symbols of the branches leaving `state`,
+/// in the tail only the terminating input is allowed.
+fn branch_inputs(state: usize, tail: bool, gs: &Gens) -> Vec<usize> {
+    if tail {
+        vec![gs.tail_input(state)]
+    } else {
+        (0..gs.inputs()).collect()
     }
-    xs[i - j]
+}
+
+/// Appends the bits of the input symbol `u` to `xs`, bit i of `u` is the bit of input i
+fn push_input(xs: &mut Vec<u8>, u: usize, k: usize) {
+    for i in 0..k {
+        xs.push(((u >> i) & 1) as u8);
+    }
+}
+
+/// The inverse of `push_input`
+fn to_input(xs: &[u8]) -> usize {
+    xs.iter().enumerate().fold(0, |acc, (i, &x)| acc | (x as usize) << i)
 }
 
 /// Same as `decode` but returns a tuple of the result and the intermediate progress
 pub fn decode_(obs: &Vec<u8>, gs: &Gens, p: f64) -> (Vec<u8>, Vec<CodePath>) {
-    let mut heap = BinaryHeap::new();
-    let l = obs.len() / gs.n - gs.m;
+    decode_with(&Bsc::new(obs, p), gs)
+}
+
+/// Same as `decode_` but the channel is described by an arbitrary metric
+pub fn decode_with<M: Metric>(metric: &M, gs: &Gens) -> (Vec<u8>, Vec<CodePath>) {
+    match decode_limited_with(metric, gs, &Limits::default()) {
+        Ok(res) => res,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Limits on the computation of the stack algorithm, `None` means unlimited
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    /// The maximum number of nodes that may be extended
+    pub max_extensions: Option<usize>,
+    /// The maximum number of paths on the stack
+    pub max_stack: Option<usize>,
+}
+
+/// The reasons for the stack algorithm to give up
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// The received word cannot be a codeword, e.g. because it is truncated
+    Malformed(String),
+    /// A computational limit was exceeded, the frame should be treated as an erasure
+    Overflow {
+        /// The number of extended nodes
+        extensions: usize,
+        /// The largest number of paths on the stack
+        stack: usize,
+        /// The deepest branch reached by any path
+        depth: usize,
+        /// The number of branches of a complete path including the tail
+        length: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Malformed(ref msg) => write!(f, "Malformed input: {}", msg),
+            DecodeError::Overflow { extensions, stack, depth, length } => {
+                write!(f,
+                       "Computational overflow after {} extensions with {} paths on the stack, \
+                        reached depth {} out of {}",
+                       extensions,
+                       stack,
+                       depth,
+                       length)
+            }
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Perform decoding using the stack algorithm with computational limits
+pub fn decode_limited(obs: &Vec<u8>, gs: &Gens, p: f64, limits: &Limits) -> Result<Vec<u8>, DecodeError> {
+    decode_limited_with(&Bsc::new(obs, p), gs, limits).map(|res| res.0)
+}
+
+/// Same as `decode_limited` but the channel is described by an arbitrary metric
+/// and the intermediate progress is returned on success
+pub fn decode_limited_with<M: Metric>(metric: &M,
+                                      gs: &Gens,
+                                      limits: &Limits)
+                                      -> Result<(Vec<u8>, Vec<CodePath>), DecodeError> {
     let mut progress = Vec::new();
+    stack_decode(metric, gs, limits, Some(&mut progress)).map(|(xs, _)| (xs.to_bits(), progress))
+}
+
+/// The computational effort of the stack algorithm
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// The number of extended nodes
+    pub extensions: usize,
+    /// The largest number of paths on the stack
+    pub max_stack: usize,
+    /// The number of extended nodes at every depth of the tree
+    pub per_depth: Vec<usize>,
+}
+
+/// Same as `decode_limited_with` but returns the computational effort instead of the progress
+pub fn decode_stats_with<M: Metric>(metric: &M,
+                                    gs: &Gens,
+                                    limits: &Limits)
+                                    -> Result<(Vec<u8>, Stats), DecodeError> {
+    stack_decode(metric, gs, limits, None).map(|(xs, stats)| (xs.to_bits(), stats))
+}
+
+/// The stack algorithm, every new path is also pushed to `progress` if available
+fn stack_decode<M: Metric>(metric: &M,
+                           gs: &Gens,
+                           limits: &Limits,
+                           mut progress: Option<&mut Vec<CodePath>>)
+                           -> Result<(BitVec, Stats), DecodeError> {
+    if metric.len() % gs.n != 0 {
+        return Err(DecodeError::Malformed(format!("received length {} is not a multiple of n = {}",
+                                                  metric.len(),
+                                                  gs.n)));
+    }
+    if metric.len() / gs.n < gs.m {
+        return Err(DecodeError::Malformed(format!("received {} branches but the tail alone has {}",
+                                                  metric.len() / gs.n,
+                                                  gs.m)));
+    }
+
+    let mut heap = BinaryHeap::new();
+    let l = metric.len() / gs.n - gs.m;
+    let mut stats = Stats {
+        extensions: 0,
+        max_stack: 1,
+        per_depth: vec![0; l + gs.m],
+    };
+    let mut depth = 0;
 
     // the single node path
     heap.push(CodePath {
-        path: Vec::new(),
-        code: Vec::new(),
+        path: BitVec::new(),
+        code: BitVec::new(),
         mu: 0f64,
+        state: 0,
     });
 
     // loop until a complete path is found
     loop {
         let best = heap.pop().unwrap();
-        if best.path.len() >= gs.m + l {
-            progress.push(best.clone());
-            return (remove_final_m(best.path, gs.m), progress);
+        if best.path.len() >= (gs.m + l) * gs.k {
+            if let Some(ref mut progress) = progress {
+                progress.push(best.clone());
+            }
+            let mut path = best.path;
+            path.truncate(l * gs.k);
+            return Ok((path, stats));
+        }
+
+        if limits.max_extensions.map_or(false, |max| stats.extensions >= max) {
+            return Err(DecodeError::Overflow {
+                extensions: stats.extensions,
+                stack: stats.max_stack,
+                depth: depth,
+                length: l + gs.m,
+            });
         }
 
-        let extended = best.extend(l, obs, gs, p);
+        stats.extensions += 1;
+        stats.per_depth[best.path.len() / gs.k] += 1;
+        let extended = best.extend(l, metric, gs);
         for path in extended {
-            progress.push(path.clone());
+            depth = ::std::cmp::max(depth, path.path.len() / gs.k);
+            if let Some(ref mut progress) = progress {
+                progress.push(path.clone());
+            }
             heap.push(path);
         }
+        stats.max_stack = ::std::cmp::max(stats.max_stack, heap.len());
+
+        if limits.max_stack.map_or(false, |max| heap.len() > max) {
+            return Err(DecodeError::Overflow {
+                extensions: stats.extensions,
+                stack: stats.max_stack,
+                depth: depth,
+                length: l + gs.m,
+            });
+        }
     }
 }
 
@@ -181,12 +810,35 @@ pub fn decode(obs: &Vec<u8>, gs: &Gens, p: f64) -> Vec<u8> {
     decode_(obs, gs, p).0
 }
 
+/// Same as `decode` but on packed bits
+pub fn decode_packed(obs: &BitVec, gs: &Gens, p: f64) -> BitVec {
+    match stack_decode(&PackedBsc::new(obs, p), gs, &Limits::default(), None) {
+        Ok((xs, _)) => xs,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Perform soft-decision decoding using the stack algorithm,
+/// `llrs` are the log-likelihood ratios ln(P(y|0) / P(y|1)) of the received symbols
+pub fn decode_soft(llrs: &[f64], gs: &Gens) -> Vec<u8> {
+    decode_with(&Llr::new(llrs), gs).0
+}
+
+/// Perform soft-decision decoding of symbols quantized into Q levels using the stack algorithm,
+/// `pr[x][q]` is the probability of receiving level `q` when `x` is transmitted,
+/// see `metric::quantize` and `metric::awgn_transitions`
+pub fn decode_quantized(ys: &[usize], pr: &[Vec<f64>; 2], gs: &Gens) -> Vec<u8> {
+    decode_with(&Quantized::new(ys, pr), gs).0
+}
+
 /// A path in the tree
 #[derive(Clone, Debug)]
 pub struct CodePath {
-    pub path: Vec<u8>,
-    pub code: Vec<u8>, // only used to display intermediate results
+    pub path: BitVec,
+    pub code: BitVec, // only used to display intermediate results
     pub mu: f64,
+    /// The encoder state at the end of the path
+    pub state: usize,
 }
 
 impl PartialEq for CodePath {
@@ -211,50 +863,33 @@ impl Ord for CodePath {
 }
 
 impl CodePath {
-    /// Consumes myself and create new branche(s),
+    /// Consumes myself and create new branche(s), one for every input symbol,
     /// this function depends on previously computed paths and fano metric.
-    fn extend(mut self, l: usize, ys: &Vec<u8>, gs: &Gens, p: f64) -> Vec<CodePath> {
+    fn extend<M: Metric>(self, l: usize, metric: &M, gs: &Gens) -> Vec<CodePath> {
         // println!("Extending {:?}", self.path);
-        let mut v = Vec::new();
-        if self.path.len() < l {
-            let mut p1 = self;
-            let mut p2 = p1.clone();
-            p1.fano(0, ys, gs, p);
-            p2.fano(1, ys, gs, p);
-            v.push(p1);
-            v.push(p2);
-        } else {
-            self.fano(0, ys, gs, p);
-            v.push(self);
+        let us = branch_inputs(self.state, self.path.len() >= l * gs.k, gs);
+        let mut v: Vec<CodePath> = (1..us.len()).map(|_| self.clone()).collect();
+        v.insert(0, self);
+        for (&u, p) in us.iter().zip(v.iter_mut()) {
+            p.fano(u, metric, gs);
         }
         v
     }
 
     /// Update the path and the fano metric,
     /// this function depends on previously computed paths and fano metric.
-    fn fano(&mut self, x: u8, ys: &Vec<u8>, gs: &Gens, p: f64) {
-        assert!(p > 0f64 && p < 1f64);
-        assert!(x == 0 || x == 1);
-
-        self.path.push(x);
-        let py = 0.5f64;
-        let r = 1f64 / gs.n as f64;
-        let _idx = self.path.len() - 1;
-        let _xs = encode_step(&self.path, gs, _idx);
-        let _ys = &ys[_idx * gs.n..(_idx + 1) * gs.n];
+    fn fano<M: Metric>(&mut self, u: usize, metric: &M, gs: &Gens) {
+        self.path.push_word(u as u64, gs.k);
+        let _idx = self.path.len() / gs.k - 1;
+        let (_state, _xs) = next_state_word(self.state, u, gs);
+        self.state = _state;
 
         // mu is the fano metric for one iteration
-        let mut mu = 0f64;
-        for (x, y) in _xs.iter().zip(_ys.iter()) {
-            if x == y {
-                mu += ((1f64 - p) / py).log2() - r;
-            } else {
-                mu += (p / py).log2() - r;
-            }
-        }
+        let mu = branch_metric(_xs, _idx, metric, gs);
 
         // copy the intermediate code for reporting
-        self.code = _xs.clone();
+        self.code = BitVec::new();
+        self.code.push_word(_xs, gs.n);
 
         // update mu to be the fano metric for the whole path
         self.mu = self.mu + mu;
@@ -262,16 +897,51 @@ impl CodePath {
     }
 }
 
+/// The Fano metric of the branch at depth `idx` with code bits `xs` (bit j is output j),
+/// the bias is the rate k/n for every code bit
+fn branch_metric<M: Metric>(xs: u64, idx: usize, metric: &M, gs: &Gens) -> f64 {
+    let r = gs.k as f64 / gs.n as f64;
+    let mut mu = 0f64;
+    for j in 0..gs.n {
+        mu += metric.symbol(idx * gs.n + j, ((xs >> j) & 1) as u8) - r;
+    }
+    mu
+}
+
 pub fn create_noise(xs: &[u8], p: f64) -> Vec<u8> {
+    create_noise_with(xs, p, &mut thread_rng())
+}
+
+/// Same as `create_noise` but draws the noise from `rng`,
+/// use `seeded_rng` to get the same errors every time
+pub fn create_noise_with<R: Rng>(xs: &[u8], p: f64, rng: &mut R) -> Vec<u8> {
     use std::u32;
     assert!(p > 0f64 && p < 1f64);
     let scaled_p = (p * u32::MAX as f64) as u32; // better to compute using Rational
-    xs.clone()
-        .into_iter()
-        .map(|&y| { if random::<u32>() < scaled_p { 1 - y } else { y } })
+    xs.iter()
+        .map(|&y| { if rng.gen::<u32>() < scaled_p { 1 - y } else { y } })
         .collect()
 }
 
+/// A random number generator that always produces the same sequence for the same seed,
+/// on every platform
+pub fn seeded_rng(seed: u64) -> XorShiftRng {
+    // xorshift must not be seeded with all zeros, so the last words are fixed
+    XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x9e37_79b9, 0x7f4a_7c15])
+}
+
+/// A fresh seed for `seeded_rng`
+pub fn random_seed() -> u64 {
+    random()
+}
+
+pub fn parse_seed(seed: &str) -> Result<u64, Error> {
+    match seed.trim().parse() {
+        Ok(seed) => Ok(seed),
+        Err(_) => Err(Error::new(ErrorKind::InvalidInput, "Parsing seed failed")),
+    }
+}
+
 fn f64_eq(a: &f64, b: &f64, eps: &f64) -> bool {
     let abs_difference = (a - b).abs();
     if abs_difference < *eps {
@@ -317,26 +987,267 @@ fn test_decode_and_fano() {
     // assert!(f64_eq(&-16.093109404391484, &worst.mu(), &1e-6));
 }
 
+#[test]
+fn test_encode_rate_2_3() {
+    // input 0 drives outputs 0 and 2, input 1 drives outputs 1 and 2
+    let gs = parse_gs("11,00,10;0,1,1").unwrap();
+    assert_eq!((gs.k, gs.n, gs.m), (2, 3, 1));
+    assert_eq!(gs.ms, vec![1, 0]);
+    assert_eq!(gs.states(), 2);
+
+    let xs = vec![1, 0, 0, 1, 1, 1];
+    assert_eq!(encode(&xs, &gs),
+               vec![1, 0, 1, 1, 1, 1, 1, 1, 0, 1, 0, 0]);
+}
+
+#[test]
+fn test_system_rate_2_3() {
+    let gs = Gens::with_inputs(vec![vec![vec![1, 1], vec![0, 1], vec![1, 1]],
+                                    vec![vec![0, 1], vec![1, 0], vec![1, 0]]]);
+    let orig = vec![1, 1, 0, 1, 0, 0, 1, 0];
+    let mut ys = encode(&orig, &gs);
+    assert_eq!(decode(&ys, &gs, 0.05), orig);
+
+    ys[4] ^= 1;
+    assert_eq!(decode(&ys, &gs, 0.05), orig);
+    assert_eq!(viterbi::decode(&ys, &gs, 0.05), orig);
+}
+
+#[test]
+fn test_long_memory() {
+    // the state of every register must fit into a usize
+    assert!(parse_gs("1 + x + x^64, 1 + x^2 + x^64").is_err());
+    let gs = parse_gs("1 + x + x^2 + x^40, 1 + x^2 + x^40").unwrap();
+    let xs = vec![1, 0, 1, 1, 0, 1];
+    let ys = encode(&xs, &gs);
+    assert_eq!(ys.len(), (xs.len() + 40) * 2);
+    assert_eq!(&ys[..4], &[1, 1, 1, 0]);
+    assert_eq!(decode(&ys, &gs, 0.05), xs);
+}
+
+#[test]
+fn test_branch_metric_rate_2_3() {
+    let gs = Gens::with_inputs(vec![vec![vec![1, 1], vec![0, 1], vec![1, 1]],
+                                    vec![vec![0, 1], vec![1, 0], vec![1, 0]]]);
+    let obs = vec![0, 0, 0, 1, 1, 0];
+    let p = 1f64 / 8f64;
+    let metric = Bsc::new(&obs, p);
+    // every bit of the branch is biased by R = 2/3
+    let (agree, differ) = ((2f64 * (1f64 - p)).log2(), (2f64 * p).log2());
+    let mu = branch_metric(0b001, 1, &metric, &gs);
+    assert!((mu - (differ + 2f64 * agree - 2f64)).abs() < 1e-9);
+    let mu = branch_metric(0b000, 0, &metric, &gs);
+    assert!((mu - (3f64 * agree - 2f64)).abs() < 1e-9);
+}
+
+#[test]
+fn test_packed() {
+    let gs = Gens::with_inputs(vec![vec![vec![1, 1], vec![0, 1], vec![1, 1]],
+                                    vec![vec![0, 1], vec![1, 0], vec![1, 0]]]);
+    let orig: Vec<u8> = (0..80).map(|i| (i % 3 == 0 || i % 5 == 0) as u8).collect();
+    let ys = encode_packed(&BitVec::from_bits(&orig), &gs);
+    assert_eq!(ys.to_bits(), encode(&orig, &gs));
+
+    let mut noisy = ys.clone();
+    noisy.set(4, 1 - noisy.get(4));
+    assert_eq!(noisy.distance(&ys), 1);
+    assert_eq!(decode_packed(&noisy, &gs, 0.05).to_bits(), orig);
+}
+
+#[test]
+fn test_parse_gs_notations() {
+    // the NASA K=7 code in octal, binary and as polynomials
+    let bin = parse_gs("1111001,1011011").unwrap().gs;
+    assert_eq!(parse_gs("0o171, 0o133").unwrap().gs, bin);
+    assert_eq!(parse_gs("K=7: 171,133").unwrap().gs, bin);
+    assert_eq!(parse_gs("1 + x + x^2 + x^3 + x^6, 1 + x^2 + x^3 + x^5 + x^6").unwrap().gs,
+               bin);
+    assert_eq!(parse_gs("1 + D^2 + D^3 + D^5 + D^6, 1111001").unwrap().gs[0], bin[1]);
+
+    // octal generators are right aligned to the constraint length
+    assert_eq!(parse_gs("0o15,0o17").unwrap().gs,
+               vec![vec![1, 1, 0, 1], vec![1, 1, 1, 1]]);
+    assert_eq!(parse_gs("K=5:23,33").unwrap().gs,
+               vec![vec![1, 0, 0, 1, 1], vec![1, 1, 0, 1, 1]]);
+    assert_eq!(parse_gs("K=3:1,5/7").unwrap().fb, Some(vec![1, 1, 1]));
+
+    let err = |gs: &str| parse_gs(gs).unwrap_err().to_string();
+    assert_eq!(err("111,1a1"), "Invalid binary digit 'a' in generator \"1a1\"");
+    assert_eq!(err("0o171,0o138"), "Invalid octal digit '8' in generator \"0o138\"");
+    assert_eq!(err("1 + x^ + x^3"), "Invalid exponent in term 'x^' in generator \"1 + x^ + x^3\"");
+    assert_eq!(err("1 + y"), "Invalid term 'y' in generator \"1 + y\"");
+    assert_eq!(err("1 + x + x"), "Repeated term 'x' in generator \"1 + x + x\"");
+    assert_eq!(err("1 ++ x"), "Empty term in generator \"1 ++ x\"");
+    assert_eq!(err("K=3:171"), "Generators are longer than the constraint length 3");
+    assert_eq!(err("L=3:7,5"), "Invalid prefix \"L=3\", expected K=<constraint length>");
+    assert_eq!(err("K=70:171,133"),
+               format!("Constraint length 70 is larger than the maximum of {}", MAX_MEMORY + 1));
+    assert_eq!(err("1 + x^99999999999"),
+               "Exponent too large in term 'x^99999999999' in generator \"1 + x^99999999999\"");
+
+    assert_eq!(err("111,000"), "Generator 2 is all zeros");
+    assert_eq!(err("0o5,0o17"), "Generator 1 has a leading coefficient of 0");
+    assert_eq!(err("11,11"), "Catastrophic code, the generators have the common factor 1 + x");
+    assert_eq!(err("110,101"), "Catastrophic code, the generators have the common factor 1 + x");
+    assert_eq!(err("11,00;01,00"), "Output 2 has only all-zero generators");
+    assert_eq!(err("01,01;11,10"), "Input 1 has no generator with a leading coefficient of 1");
+    assert_eq!(err("1;1"), "The number of inputs 2 is larger than the number of outputs 1");
+    assert_eq!(err("11,11;11,11"),
+               "The generator matrix has rank below k, the code cannot be inverted");
+    assert_eq!(err(&vec!["1"; MAX_OUTPUTS + 1].join(",")),
+               format!("At most {} outputs are supported", MAX_OUTPUTS));
+}
+
+#[test]
+fn test_encode_recursive() {
+    // the systematic code (1, 5/7), the tail is not all zeros because of the feedback
+    let gs = parse_gs("1,101/111").unwrap();
+    assert_eq!(gs.gs, vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    assert_eq!(gs.fb, Some(vec![1, 1, 1]));
+
+    let xs = vec![1, 0, 1, 1];
+    let ys = encode(&xs, &gs);
+    assert_eq!(ys, vec![1, 1, 0, 1, 1, 0, 1, 0, 0, 1, 1, 1]);
+    assert_eq!(encode_from(0, &[1, 0, 1, 1, 0, 1], &gs).0, 0);
+
+    let p = 0.05;
+    let mut noisy_ys = ys.clone();
+    noisy_ys[3] ^= 1;
+    assert_eq!(decode(&noisy_ys, &gs, p), xs);
+    assert_eq!(viterbi::decode(&noisy_ys, &gs, p), xs);
+    assert_eq!(fano::decode(&noisy_ys, &gs, p, 1.0), xs);
+    assert_eq!(bcjr::hard_decision(&bcjr::decode(&noisy_ys, &gs, p)), xs);
+
+    assert!(parse_gs("1,101/111,11/101").is_err());
+    assert!(parse_gs("1,101/011").is_err());
+    assert!(parse_gs("1,101/111;1,1").is_err());
+}
+
+#[test]
+fn test_decode_soft() {
+    let xs = vec![1, 0, 1, 1, 0, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let mut llrs: Vec<f64> = encode(&xs, &gs)
+        .into_iter()
+        .map(|y| if y == 0 { 2f64 } else { -2f64 })
+        .collect();
+
+    // unreliable errors are outweighed by the reliable symbols around them
+    llrs[2] = -llrs[2] / 10f64;
+    llrs[3] = -llrs[3] / 10f64;
+    llrs[9] = -llrs[9] / 10f64;
+    assert_eq!(decode_soft(&llrs, &gs), xs);
+}
+
+#[test]
+fn test_decode_quantized() {
+    use channel::{Channel, Awgn};
+    use metric::{quantize, awgn_transitions};
+
+    let xs = vec![1, 0, 1, 1, 0, 1, 0, 0, 1, 1, 1, 0];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let ys = encode(&xs, &gs);
+    let sigma = 0.7f64;
+    let received = Awgn::with_sigma(sigma).transmit(&ys, &mut seeded_rng(3));
+    let levels = quantize(&received, 8, 0.5);
+
+    // the hard decisions of the levels contain errors
+    let hard: Vec<u8> = levels.iter().map(|&q| (q >= 4) as u8).collect();
+    assert!(hard.iter().zip(ys.iter()).any(|(a, b)| a != b));
+    assert_eq!(decode_quantized(&levels, &awgn_transitions(8, 0.5, sigma), &gs), xs);
+}
+
+#[test]
+fn test_decode_limited() {
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let xs = vec![1, 0, 1, 1, 0, 1];
+    let ys = encode(&xs, &gs);
+    let p = 0.1;
+
+    assert_eq!(decode_limited(&ys, &gs, p, &Limits::default()), Ok(xs.clone()));
+    assert_eq!(decode_limited(&ys[..15].to_vec(), &gs, p, &Limits::default()),
+               Err(DecodeError::Malformed("received length 15 is not a multiple of n = 2"
+                   .to_string())));
+    assert!(match decode_limited(&ys[..2].to_vec(), &gs, p, &Limits::default()) {
+        Err(DecodeError::Malformed(_)) => true,
+        _ => false,
+    });
+
+    // without errors the correct path is extended straight away
+    let limits = Limits {
+        max_extensions: Some(8),
+        max_stack: None,
+    };
+    assert_eq!(decode_limited(&ys, &gs, p, &limits), Ok(xs.clone()));
+
+    // many errors at the start need a lot of computation
+    let mut noisy_ys = ys.clone();
+    for i in 0..5 {
+        noisy_ys[i] ^= 1;
+    }
+    let limits = Limits {
+        max_extensions: Some(4),
+        max_stack: None,
+    };
+    assert_eq!(decode_limited(&noisy_ys, &gs, p, &limits),
+               Err(DecodeError::Overflow {
+                   extensions: 4,
+                   stack: 5,
+                   depth: 3,
+                   length: 8,
+               }));
+    let limits = Limits {
+        max_extensions: None,
+        max_stack: Some(3),
+    };
+    assert!(decode_limited(&noisy_ys, &gs, p, &limits).is_err());
+}
+
+#[test]
+fn test_decode_stats() {
+    let obs = vec![0, 0, 1, 0, 0, 1, 0, 1, 1, 1, 0, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let p = 1f64 / 16f64;
+    let (xs, stats) = decode_stats_with(&Bsc::new(&obs, p), &gs, &Limits::default()).unwrap();
+    assert_eq!(xs, decode(&obs, &gs, p));
+    assert_eq!(stats.per_depth.len(), 4);
+    assert_eq!(stats.per_depth.iter().sum::<usize>(), stats.extensions);
+    assert!(stats.per_depth.iter().all(|&c| c >= 1));
+
+    // every extension of the first 2 branches adds one path to the stack
+    let progress = decode_(&obs, &gs, p).1;
+    assert_eq!(progress.len(), 2 * (stats.per_depth[0] + stats.per_depth[1]) +
+                               stats.per_depth[2] + stats.per_depth[3] + 1);
+}
+
 #[test]
 fn test_noise() {
     const CNT: usize = 1000000;
     let p = 0.1;
-    let len = create_noise(&[0; CNT], 0.1)
+    let len = create_noise_with(&[0; CNT], 0.1, &mut seeded_rng(0))
         .into_iter()
         .filter(|&x| x == 1)
         .collect::<Vec<u8>>()
         .len();
-    assert!(f64_eq(&p, &(len as f64 / CNT as f64), &1e-3))
+    assert!(f64_eq(&p, &(len as f64 / CNT as f64), &1e-3));
+
+    // the same seed gives the same errors
+    let xs = [0; 100];
+    assert_eq!(create_noise_with(&xs, p, &mut seeded_rng(15)),
+               create_noise_with(&xs, p, &mut seeded_rng(15)));
+    assert!(create_noise_with(&xs, p, &mut seeded_rng(15)) !=
+            create_noise_with(&xs, p, &mut seeded_rng(16)));
 }
 
 #[test]
 fn test_system() {
     // TODO randomise these
+    const SEED: u64 = 0;
     let orig = vec![0, 1, 0, 1];
     let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
     let p = 1f64 / 10f64;
 
-    let ys = create_noise(&encode(&orig, &gs), p);
+    let ys = create_noise_with(&encode(&orig, &gs), p, &mut seeded_rng(SEED));
     // println!("ys {:?}", ys);
     let xs = decode(&ys, &gs, p);
     assert_eq!(orig, xs);
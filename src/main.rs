@@ -11,6 +11,9 @@ use gtk::prelude::*;
 
 const STEP_PX: f64 = 120.;
 
+// drawing more paths than this is not useful
+const MAX_EXTENSIONS: usize = 2000;
+
 // make pack_start easier for default values
 macro_rules! pack_start {
     ($b:ident, $e:expr, $f:expr => $( $i:ident ),+) => {
@@ -77,7 +80,7 @@ fn append_before<T: Copy>(xs: &Vec<T>, sep: T) -> Vec<T> {
 }
 
 // markup in pango
-fn format_gens(gs: &Vec<Vec<u8>>) -> String {
+fn format_gens(gs: &cs::Gens) -> String {
     let format_gen = |g: &Vec<u8>| -> String {
         let mut res = "".to_string();
         for (i, val) in g.iter().enumerate() {
@@ -98,8 +101,15 @@ fn format_gens(gs: &Vec<Vec<u8>>) -> String {
     };
 
     let mut res = "".to_string();
-    for g in gs {
-        res.push_str(&format_gen(g));
+    for (i, g) in gs.gs.iter().enumerate() {
+        // label the input and output of every generator for rate k/n codes
+        if gs.k > 1 {
+            res.push_str(&format!("g<sub>{},{}</sub> = ", i / gs.n + 1, i % gs.n + 1));
+        }
+        match gs.fb {
+            Some(ref f) => res.push_str(&format!("({}) / ({})", format_gen(g), format_gen(f))),
+            None => res.push_str(&format_gen(g)),
+        }
         res.push('\n');
     }
     res.pop().unwrap(); // remove the final \n
@@ -137,14 +147,33 @@ fn encode_main(xs: &str, gs: &str) -> Result<Vec<u8>, Error> {
     // shadow the input params
     let xs = try!(cs::parse_bin(&xs));
     let gs = try!(cs::parse_gs(&gs));
+    try!(check_input_len(&xs, &gs));
     Ok(cs::encode(&xs, &gs))
 }
 
+fn receive_main(tx: &str, pr: &str, seed: &str) -> Result<Vec<u8>, Error> {
+    // shadow the input params
+    let ys = try!(cs::parse_bin(tx));
+    let pr = try!(cs::parse_pr(pr));
+    let seed = try!(cs::parse_seed(seed));
+    Ok(cs::create_noise_with(&ys, pr, &mut cs::seeded_rng(seed)))
+}
+
+// every input of a rate k/n code needs the same number of bits
+fn check_input_len(xs: &Vec<u8>, gs: &cs::Gens) -> Result<(), Error> {
+    if xs.len() % gs.k != 0 {
+        return Err(Error::new(ErrorKind::InvalidInput,
+                              "Input length must be a multiple of the number of inputs"));
+    }
+    Ok(())
+}
+
 fn run_stack_algo(xs: &str, gs: &str, pr: &str, rx: &str) -> Result<cs::StackResults, Error> {
     // shadow the input params
     let xs = try!(cs::parse_bin(xs));
     let gs = try!(cs::parse_gs(gs));
     let pr = try!(cs::parse_pr(pr));
+    try!(check_input_len(&xs, &gs));
     let ys = cs::encode(&xs, &gs);
 
     let noisy_ys = try!(cs::parse_bin(rx));
@@ -153,7 +182,14 @@ fn run_stack_algo(xs: &str, gs: &str, pr: &str, rx: &str) -> Result<cs::StackRes
                               "Transmitted and received bits have different lengths"));
     }
 
-    let (path, paths) = cs::decode_(&noisy_ys, &gs, pr);
+    let limits = cs::Limits {
+        max_extensions: Some(MAX_EXTENSIONS),
+        max_stack: None,
+    };
+    let (path, paths) = match cs::decode_limited_with(&cs::metric::Bsc::new(&noisy_ys, pr), &gs, &limits) {
+        Ok(res) => res,
+        Err(e) => return Err(Error::new(ErrorKind::Other, e.to_string())),
+    };
 
     Ok(cs::StackResults {
         gens: gs,
@@ -180,7 +216,8 @@ impl DrawingWindow {
 
     fn run(&self, res: cs::StackResults) {
         // properties derived from results
-        let drawing_w = max_len(&res.paths) * STEP_PX as usize + 150;
+        let k = res.gens.k;
+        let drawing_w = max_len(&res.paths) / k * STEP_PX as usize + 150;
         let max_lvl = res.paths.len();
         let decoded_l = res.decoded.len();
 
@@ -215,11 +252,11 @@ impl DrawingWindow {
         let data_out = gtk::Label::new(Some("n/a"));
         let data_m = gtk::Label::new(Some(&res.gens.m.to_string()));
         let actual_rate = decoded_l as f64 / res.encoded.len() as f64;
-        let asymptotic_rate = 1.0 / res.gens.n as f64;
+        let asymptotic_rate = k as f64 / res.gens.n as f64;
         let data_actual_rate = gtk::Label::new(Some(&format!("{:.2}", actual_rate)));
         let data_asymptotic_rate = gtk::Label::new(Some(&format!("{:.2}", asymptotic_rate)));
         let data_gens = gtk::Label::new(None);
-        data_gens.set_markup(&format_gens(&res.gens.gs));
+        data_gens.set_markup(&format_gens(&res.gens));
 
         let lbl_info = gtk::Label::new(Some("User guide:\n\
                                              \n\
@@ -329,7 +366,7 @@ impl DrawingWindow {
                     data_out.set_text("n/a");
                 }
 
-                DrawingWindow::draw_path(cr, h / 2., 1, path.path.clone(), &path.code, path.mu, decoded_l);
+                DrawingWindow::draw_path(cr, h / 2., 1, path.path.to_bits(), &path.code.to_bits(), path.mu, decoded_l / k, k);
             }
 
             Inhibit(false)
@@ -350,7 +387,8 @@ impl DrawingWindow {
                  mut path: Vec<u8>,
                  code: &Vec<u8>,
                  mu: f64,
-                 l: usize) {
+                 l: usize,
+                 k: usize) {
         if path.is_empty() {
             cr.rel_move_to(0., -15.); // no need to move back because we're return at the end
             cr.set_font_size(15.);
@@ -360,23 +398,31 @@ impl DrawingWindow {
             return;
         }
 
-        let p = path.remove(0);
+        // the k bits of the branch form the input symbol, bit i is the bit of input i
+        let p = path.drain(..k).enumerate().fold(0, |acc, (i, x)| {
+            match x {
+                0 | 1 => acc | (x as usize) << i,
+                _ => panic!("Must be 0 or 1"),
+            }
+        });
         let (x, y) = cr.get_current_point();
-        let h = h / 2.0; // shadow
+        let branches = 1 << k;
+        let h = h / branches as f64; // shadow
         cr.arc(x, y, 5., 0., 2. * ::std::f64::consts::PI);
 
-        // draw straight line if we're at the last m positions
+        // draw straight line if we're at the last m positions,
+        // otherwise spread the branches evenly from the top (0) to the bottom
         if lvl > l {
             cr.set_dash(&[], 0.);
             cr.rel_line_to(STEP_PX, 0.);
-        } else if p == 0 {
-            cr.set_dash(&[], 0.);
-            cr.rel_line_to(STEP_PX, -h);
-        } else if p == 1 {
-            cr.set_dash(&[8.0], 0.);
-            cr.rel_line_to(STEP_PX, h);
         } else {
-            panic!("Must be 0 or 1");
+            if p == 0 {
+                cr.set_dash(&[], 0.);
+            } else {
+                cr.set_dash(&[8.0], 0.);
+            }
+            let offset = 2. * p as f64 / (branches - 1) as f64 - 1.;
+            cr.rel_line_to(STEP_PX, offset * h * (branches / 2) as f64);
         }
 
         // prepare the current point for the recursive step
@@ -385,7 +431,7 @@ impl DrawingWindow {
         cr.move_to(x, y);
 
         // recursive step
-        DrawingWindow::draw_path(cr, h, lvl + 1, path, code, mu, l)
+        DrawingWindow::draw_path(cr, h, lvl + 1, path, code, mu, l, k)
     }
 }
 
@@ -404,6 +450,7 @@ impl MainWindow {
         let box_main = gtk::Box::new(Orientation::Vertical, 0);
         let box_tx = gtk::Box::new(Orientation::Horizontal, 0);
         let box_rx = gtk::Box::new(Orientation::Horizontal, 0);
+        let box_gs = gtk::Box::new(Orientation::Horizontal, 0);
         let sep_margin = 20;
 
         // input
@@ -417,8 +464,17 @@ impl MainWindow {
 
         // generators
         let lbl_gs = gtk::Label::new(None);
-        lbl_gs.set_markup("Generator coefficients,\ni.e. 1 + x<sup>2</sup> + x<sup>3</sup> = 1011, separated by commas.");
+        lbl_gs.set_markup("Generator coefficients separated by commas, i.e. 1 + x<sup>2</sup> + x<sup>3</sup> \
+                           can be written as 1011, 1 + x^2 + x^3 or in octal as 0o13 or K=4:13.\n\
+                           Separate the generators of every input by semicolons for rate k/n codes,\n\
+                           divide by the feedback polynomial for recursive codes, i.e. 1,101/111.\n\
+                           Catastrophic codes and generators that are all zeros or start with 0 are rejected.");
         let ent_gs = gtk::Entry::new_with_buffer(&gtk::EntryBuffer::new(Some("111,110,101")));
+        let cmb_gs = gtk::ComboBoxText::new();
+        for code in cs::catalogue::CODES {
+            cmb_gs.append_text(code.name);
+        }
+        cmb_gs.set_tooltip_text(Some("Fill in the generators of a well-known code."));
         let sep_gs = gtk::Separator::new(Orientation::Horizontal);
         lbl_gs.set_halign(Align::Start);
         sep_gs.set_valign(Align::Center);
@@ -449,11 +505,15 @@ impl MainWindow {
 
         // received
         let lbl_rx = gtk::Label::new(Some("Received bits,\n\
-                                           randomise the bits by clicking the icon."));
+                                           randomise the bits by clicking the icon, \
+                                           or press enter in the seed to repeat the errors of that seed."));
         let ent_rx = gtk::Entry::new_with_buffer(&gtk::EntryBuffer::new(None));
         let btn_rx = gtk::Button::new_from_icon_name("media-playlist-shuffle", 2);
         btn_rx.set_tooltip_text(Some("Randomise the received code based on the transmitted code \
-                                      and the error probability, assuming a BSC."));
+                                      and the error probability with a new seed, assuming a BSC."));
+        let ent_seed = gtk::Entry::new_with_buffer(&gtk::EntryBuffer::new(None));
+        ent_seed.set_placeholder_text(Some("seed"));
+        ent_seed.set_tooltip_text(Some("The seed of the errors, press enter to generate them again."));
         let sep_rx = gtk::Separator::new(Orientation::Horizontal);
         lbl_rx.set_halign(Align::Start);
         sep_rx.set_valign(Align::Center);
@@ -465,7 +525,9 @@ impl MainWindow {
 
         // arrange widgets
         pack_start!(box_main, false, false => lbl_xs, ent_xs, sep_xs);
-        pack_start!(box_main, false, false => lbl_gs, ent_gs, sep_gs);
+        box_gs.pack_start(&ent_gs, true, true, 0);
+        box_gs.pack_end(&cmb_gs, false, false, 0);
+        pack_start!(box_main, false, false => lbl_gs, box_gs, sep_gs);
         pack_start!(box_main, false, false => lbl_pr, ent_pr, sep_pr);
 
         box_tx.pack_start(&ent_tx, true, true, 0);
@@ -474,11 +536,19 @@ impl MainWindow {
 
         box_rx.pack_start(&ent_rx, true, true, 0);
         box_rx.pack_end(&btn_rx, false, false, 0);
+        box_rx.pack_end(&ent_seed, false, false, 0);
         pack_start!(box_main, false, false => lbl_rx, box_rx, sep_rx);
 
         box_main.pack_end(&btn_start, false, false, 0);
 
         // call backs
+        cmb_gs.connect_changed(clone!(ent_gs => move |cmb_gs| {
+            let code = cmb_gs.get_active_text().and_then(|name| cs::catalogue::find(&name));
+            if let Some(code) = code {
+                ent_gs.set_text(code.gs);
+            }
+        }));
+
         btn_tx.connect_clicked(clone!(ent_xs, ent_gs, ent_tx, window => move |_| {
             let xs = ent_xs.get_buffer().get_text();
             let gs = ent_gs.get_buffer().get_text();
@@ -491,15 +561,14 @@ impl MainWindow {
             ent_tx.set_text(&spacing(&format_bin(&ys), n));
         }));
 
-        btn_rx.connect_clicked(clone!(ent_pr, ent_tx, ent_rx, ent_gs, window => move |_| {
-            let ys = error_dialog!(window, cs::parse_bin(
-                &match ent_tx.get_text() {
-                    Some(x) => x,
-                    None    => "".to_string(),
-                }
-            ));
-            let pr = error_dialog!(window, cs::parse_pr(&ent_pr.get_buffer().get_text()));
-            let noisy_ys = cs::create_noise(&ys, pr);
+        ent_seed.connect_activate(clone!(ent_pr, ent_tx, ent_rx, ent_gs, window => move |ent_seed| {
+            let tx = match ent_tx.get_text() {
+                Some(x) => x,
+                None    => "".to_string(),
+            };
+            let noisy_ys = error_dialog!(window, receive_main(&tx,
+                                                              &ent_pr.get_buffer().get_text(),
+                                                              &ent_seed.get_buffer().get_text()));
 
             // shouldn't fail because we parsed gs before already
             // TODO consider storing gs as an attribute
@@ -508,6 +577,12 @@ impl MainWindow {
             ent_rx.set_text(&spacing(&format_bin(&noisy_ys), n));
         }));
 
+        // a new seed is shown before generating the errors so that they can be repeated
+        btn_rx.connect_clicked(clone!(ent_seed => move |_| {
+            ent_seed.set_text(&cs::random_seed().to_string());
+            ent_seed.activate();
+        }));
+
         btn_start.connect_clicked(clone!(ent_xs, ent_gs, ent_pr, ent_rx, window => move |_| {
             let xs = ent_xs.get_buffer().get_text();
             let gs = ent_gs.get_buffer().get_text();
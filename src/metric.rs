@@ -0,0 +1,216 @@
+//! Channel metrics used by the decoders.
+//!
+//! The Fano metric of a branch is the sum of log2(P(y|x) / P(y)) over the received
+//! symbols y of the branch minus the rate bias. The channel dependent term is provided
+//! by a `Metric`, so the same decoders work on hard bits, LLRs or quantized symbols.
+
+use std::f64;
+use bitvec::BitVec;
+
+/// The channel dependent part of the Fano metric
+pub trait Metric {
+    /// Returns log2(P(y_i | x) / P(y_i)) for the i-th received symbol assuming code bit `x`
+    fn symbol(&self, i: usize, x: u8) -> f64;
+
+    /// The number of received symbols
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hard decisions received over a binary symmetric channel with crossover probability `p`
+pub struct Bsc<'a> {
+    ys: &'a [u8],
+    p: f64,
+}
+
+impl<'a> Bsc<'a> {
+    pub fn new(ys: &'a [u8], p: f64) -> Bsc<'a> {
+        assert!(p > 0f64 && p < 1f64);
+        Bsc { ys: ys, p: p }
+    }
+}
+
+impl<'a> Metric for Bsc<'a> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        bsc_symbol(self.ys[i], x, self.p)
+    }
+
+    fn len(&self) -> usize {
+        self.ys.len()
+    }
+}
+
+/// Same as `Bsc` but the hard decisions are packed
+pub struct PackedBsc<'a> {
+    ys: &'a BitVec,
+    p: f64,
+}
+
+impl<'a> PackedBsc<'a> {
+    pub fn new(ys: &'a BitVec, p: f64) -> PackedBsc<'a> {
+        assert!(p > 0f64 && p < 1f64);
+        PackedBsc { ys: ys, p: p }
+    }
+}
+
+impl<'a> Metric for PackedBsc<'a> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        bsc_symbol(self.ys.get(i), x, self.p)
+    }
+
+    fn len(&self) -> usize {
+        self.ys.len()
+    }
+}
+
+fn bsc_symbol(y: u8, x: u8, p: f64) -> f64 {
+    let py = 0.5f64;
+    if x == y {
+        ((1f64 - p) / py).log2()
+    } else {
+        (p / py).log2()
+    }
+}
+
+/// Soft decisions given as log-likelihood ratios ln(P(y|0) / P(y|1)),
+/// i.e. positive values favour 0
+pub struct Llr<'a> {
+    llrs: &'a [f64],
+}
+
+impl<'a> Llr<'a> {
+    pub fn new(llrs: &'a [f64]) -> Llr<'a> {
+        Llr { llrs: llrs }
+    }
+}
+
+impl<'a> Metric for Llr<'a> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        // with equiprobable inputs P(y|x) / P(y) = 2 P(x|y) = 2 / (1 + e^(-L)) for x = 0
+        let l = if x == 0 { self.llrs[i] } else { -self.llrs[i] };
+        1f64 - log2_1p_exp(-l)
+    }
+
+    fn len(&self) -> usize {
+        self.llrs.len()
+    }
+}
+
+/// Symbols quantized into Q levels, with the metric computed from the transition probabilities
+pub struct Quantized<'a> {
+    ys: &'a [usize],
+    table: Vec<[f64; 2]>,
+}
+
+impl<'a> Quantized<'a> {
+    /// `pr[x][q]` is the probability of receiving level `q` when `x` is transmitted
+    pub fn new(ys: &'a [usize], pr: &[Vec<f64>; 2]) -> Quantized<'a> {
+        assert_eq!(pr[0].len(), pr[1].len());
+        let table = pr[0]
+            .iter()
+            .zip(pr[1].iter())
+            .map(|(p0, p1)| {
+                let py = 0.5f64 * (p0 + p1);
+                [(p0 / py).log2(), (p1 / py).log2()]
+            })
+            .collect();
+        Quantized { ys: ys, table: table }
+    }
+}
+
+impl<'a> Metric for Quantized<'a> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        self.table[self.ys[i]][x as usize]
+    }
+
+    fn len(&self) -> usize {
+        self.ys.len()
+    }
+}
+
+/// Computes log2(1 + e^x) without overflowing for large x
+fn log2_1p_exp(x: f64) -> f64 {
+    if x > 30f64 {
+        x / f64::consts::LN_2
+    } else {
+        x.exp().ln_1p() / f64::consts::LN_2
+    }
+}
+
+/// The thresholds of a uniform quantizer with `q` levels, from the highest to the lowest
+fn thresholds(q: usize, step: f64) -> Vec<f64> {
+    (0..q - 1).map(|k| (q as f64 / 2f64 - 1f64 - k as f64) * step).collect()
+}
+
+/// Quantizes BPSK symbols (0 is sent as +1 and 1 as -1) into `q` levels of width `step`,
+/// level 0 is the most reliable 0 and level q - 1 the most reliable 1.
+pub fn quantize(ys: &[f64], q: usize, step: f64) -> Vec<usize> {
+    assert!(q >= 2);
+    let ts = thresholds(q, step);
+    ys.iter().map(|y| ts.iter().filter(|&t| y < t).count()).collect()
+}
+
+/// The transition probabilities of `quantize` for BPSK over an AWGN channel
+/// with noise standard deviation `sigma`, usable with `Quantized::new`.
+pub fn awgn_transitions(q: usize, step: f64, sigma: f64) -> [Vec<f64>; 2] {
+    assert!(q >= 2 && sigma > 0f64);
+    let ts = thresholds(q, step);
+    let pr = |mean: f64| -> Vec<f64> {
+        // P(y < t) for y ~ N(mean, sigma^2)
+        let cdf = |t: f64| 0.5f64 * erfc((mean - t) / (sigma * f64::consts::SQRT_2));
+        (0..q)
+            .map(|j| {
+                let upper = if j == 0 { 1f64 } else { cdf(ts[j - 1]) };
+                let lower = if j == q - 1 { 0f64 } else { cdf(ts[j]) };
+                upper - lower
+            })
+            .collect()
+    };
+    [pr(1f64), pr(-1f64)]
+}
+
+/// The complementary error function, with a fractional error below 1.2e-7 (Numerical Recipes)
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1f64 / (1f64 + 0.5f64 * z);
+    let coeffs = [-1.26551223, 1.00002368, 0.37409196, 0.09678418, -0.18628806, 0.27886807,
+                  -1.13520398, 1.48851587, -0.82215223, 0.17087277];
+    let poly = coeffs.iter().rev().fold(0f64, |acc, c| c + t * acc);
+    let r = t * (-z * z + poly).exp();
+    if x >= 0f64 { r } else { 2f64 - r }
+}
+
+#[test]
+fn test_llr_matches_bsc() {
+    let p = 0.1f64;
+    let ys = vec![0, 1];
+    let llr = ((1f64 - p) / p).ln();
+    let llrs = vec![llr, -llr];
+    let (hard, soft) = (Bsc::new(&ys, p), Llr::new(&llrs));
+    for i in 0..2 {
+        for x in 0..2 {
+            assert!((hard.symbol(i, x) - soft.symbol(i, x)).abs() < 1e-9);
+        }
+    }
+    assert!(Llr::new(&[0f64]).symbol(0, 1).abs() < 1e-9);
+}
+
+#[test]
+fn test_quantize() {
+    let ys = vec![2.5, 0.7, 0.2, -0.2, -0.7, -2.5];
+    assert_eq!(quantize(&ys, 2, 1.0), vec![0, 0, 0, 1, 1, 1]);
+    assert_eq!(quantize(&ys, 4, 0.5), vec![0, 0, 1, 2, 3, 3]);
+
+    let pr = awgn_transitions(8, 0.5, 0.8);
+    for x in 0..2 {
+        assert!((pr[x].iter().sum::<f64>() - 1f64).abs() < 1e-6);
+    }
+    // symmetric channel
+    for j in 0..8 {
+        assert!((pr[0][j] - pr[1][7 - j]).abs() < 1e-6);
+    }
+    assert!((erfc(1f64) - 0.157299207).abs() < 1e-6);
+}
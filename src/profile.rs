@@ -0,0 +1,116 @@
+//! Column distance function and distance profile.
+//!
+//! The column distance d_j is the smallest weight of the first j + 1 branches of a code
+//! sequence whose first input is nonzero. It grows towards the free distance, and the first
+//! m + 1 values form the distance profile. A sequential decoder only looks at the start of
+//! the tree, so codes whose profile grows slowly let wrong paths stay on the stack for long.
+
+use super::Gens;
+use trellis::Trellis;
+
+/// Computes the column distances d_0 to d_`j`
+pub fn column_distances(gs: &Gens, j: usize) -> Vec<usize> {
+    let trellis = Trellis::new(gs);
+    let unreachable = usize::max_value();
+
+    // the smallest weight of the paths into every state, the first input is nonzero
+    let mut dist = vec![unreachable; trellis.states()];
+    for b in trellis.branches(0).skip(1) {
+        dist[b.to] = ::std::cmp::min(dist[b.to], b.weight());
+    }
+
+    let mut res = vec![*dist.iter().min().unwrap()];
+    for _ in 0..j {
+        let mut next = vec![unreachable; trellis.states()];
+        for b in trellis.all_branches().filter(|b| dist[b.from] != unreachable) {
+            next[b.to] = ::std::cmp::min(next[b.to], dist[b.from] + b.weight());
+        }
+        dist = next;
+        res.push(*dist.iter().min().unwrap());
+    }
+    res
+}
+
+/// The distance profile, i.e. the column distances d_0 to d_m.
+/// A profile is better than another if it is larger at the first position where they differ,
+/// which is the order of `Vec<usize>`.
+pub fn distance_profile(gs: &Gens) -> Vec<usize> {
+    column_distances(gs, gs.m)
+}
+
+/// The best distance profile of a rate 1/n code with memory m.
+/// The search keeps only the generator prefixes with the best profile so far,
+/// because d_j does not depend on the coefficients of the powers above j.
+pub fn optimum_profile(n: usize, m: usize) -> Vec<usize> {
+    // prefixes of the generators with equal lengths, the generators are kept sorted
+    // since swapping outputs does not change the distances
+    let mut prefixes = vec![vec![vec![1]; n]];
+    let mut res = vec![n];
+    for j in 1..m + 1 {
+        let mut best = 0;
+        let mut next = Vec::new();
+        for prefix in &prefixes {
+            for bits in 0..1 << n {
+                let mut gs: Vec<Vec<u8>> = prefix.clone();
+                for (i, g) in gs.iter_mut().enumerate() {
+                    g.push(((bits >> i) & 1) as u8);
+                }
+                if gs.windows(2).any(|w| w[0] > w[1]) {
+                    continue;
+                }
+                let d = *column_distances(&Gens::new(gs.clone()), j).last().unwrap();
+                if d > best {
+                    best = d;
+                    next.clear();
+                }
+                if d == best {
+                    next.push(gs);
+                }
+            }
+        }
+        res.push(best);
+        prefixes = next;
+    }
+    res
+}
+
+/// Whether the distance profile falls behind the optimum profile within the first half of
+/// the memory, where it matters most for sequential decoding.
+/// Returns `None` for codes with more than one input, which are not searched.
+pub fn is_poor_profile(gs: &Gens) -> Option<bool> {
+    if gs.k > 1 {
+        return None;
+    }
+    let profile = distance_profile(gs);
+    let optimum = optimum_profile(gs.n, gs.m);
+    Some((0..gs.m / 2 + 1).any(|j| profile[j] < optimum[j]))
+}
+
+#[test]
+fn test_column_distances() {
+    use distance::free_distance;
+
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let ds = column_distances(&gs, 10);
+    assert_eq!(&ds[..3], &[2, 3, 3]);
+    assert_eq!(distance_profile(&gs), vec![2, 3, 3]);
+    assert!(ds.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(ds[10], free_distance(&gs));
+
+    // the recursive systematic encoder has the same code but a different tree
+    let gs = Gens::systematic(vec![1, 1, 1], vec![vec![1, 0, 1]]);
+    assert_eq!(distance_profile(&gs), vec![2, 3, 3]);
+}
+
+#[test]
+fn test_optimum_profile() {
+    assert_eq!(optimum_profile(2, 2), vec![2, 3, 3]);
+    assert_eq!(optimum_profile(2, 3), vec![2, 3, 3, 4]);
+
+    assert_eq!(is_poor_profile(&Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]])),
+               Some(false));
+    // the second branch of 1 + x^2 + x^3, 1 + x^3 has no weight after a single 1
+    let gs = Gens::new(vec![vec![1, 0, 1, 1], vec![1, 0, 0, 1]]);
+    assert_eq!(column_distances(&gs, 1), vec![2, 2]);
+    assert_eq!(is_poor_profile(&gs), Some(true));
+}
@@ -0,0 +1,165 @@
+//! Puncturing of convolutional codes for higher code rates.
+//!
+//! A puncturing pattern has one row for every output of the mother code and
+//! one column for every branch in the period, a 0 deletes the bit from the
+//! transmitted code. The decoder treats the deleted bits as erasures.
+
+use std::io::{Error, ErrorKind};
+use super::{Gens, encode as encode_mother, decode_with};
+use metric::{Metric, Bsc};
+
+/// A periodic puncturing pattern
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    /// `rows[j][t]` is 1 when output j of branch t (modulo the period) is transmitted
+    pub rows: Vec<Vec<u8>>,
+}
+
+impl Pattern {
+    /// Every row must have the same length and every column must keep at least one bit
+    pub fn new(rows: Vec<Vec<u8>>) -> Result<Pattern, Error> {
+        if rows.is_empty() || rows[0].is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Empty puncturing pattern"));
+        }
+        if rows.iter().any(|row| row.len() != rows[0].len()) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "Rows of the puncturing pattern have different lengths"));
+        }
+        if rows.iter().flat_map(|row| row.iter()).any(|&x| x != 0 && x != 1) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "Puncturing pattern must be 0 or 1"));
+        }
+        if (0..rows[0].len()).any(|t| rows.iter().all(|row| row[t] == 0)) {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "Every branch must keep at least one bit"));
+        }
+        Ok(Pattern { rows: rows })
+    }
+
+    /// The rate 2/3 pattern of IEEE 802.11 for the rate 1/2 mother code
+    pub fn rate_2_3() -> Pattern {
+        Pattern { rows: vec![vec![1, 1], vec![1, 0]] }
+    }
+
+    /// The rate 3/4 pattern of IEEE 802.11 for the rate 1/2 mother code
+    pub fn rate_3_4() -> Pattern {
+        Pattern { rows: vec![vec![1, 1, 0], vec![1, 0, 1]] }
+    }
+
+    /// The number of outputs of the mother code
+    pub fn n(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The number of branches in one period
+    pub fn period(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    fn keep(&self, i: usize) -> bool {
+        let n = self.n();
+        self.rows[i % n][(i / n) % self.period()] == 1
+    }
+
+    /// The code rate after puncturing the mother code `gs`
+    pub fn rate(&self, gs: &Gens) -> f64 {
+        assert_eq!(gs.n, self.n());
+        let kept: usize = self.rows.iter().map(|row| row.iter().filter(|&&x| x == 1).count()).sum();
+        (self.period() * gs.k) as f64 / kept as f64
+    }
+
+    /// Deletes the bits (or symbols) of the mother code `ys` according to the pattern
+    pub fn puncture<T: Clone>(&self, ys: &[T]) -> Vec<T> {
+        assert_eq!(ys.len() % self.n(), 0);
+        ys.iter()
+            .enumerate()
+            .filter(|&(i, _)| self.keep(i))
+            .map(|(_, y)| y.clone())
+            .collect()
+    }
+
+    /// Wraps the metric of the received punctured code so that it can be used
+    /// with the mother code, the deleted bits become erasures
+    pub fn depuncture<'a, M: Metric>(&self, metric: &'a M) -> Depunctured<'a, M> {
+        // every period keeps at least one bit so the length of the mother code is unique
+        let mut map = Vec::new();
+        let mut j = 0;
+        while j < metric.len() || map.len() % self.n() != 0 {
+            if self.keep(map.len()) {
+                map.push(Some(j));
+                j += 1;
+            } else {
+                map.push(None);
+            }
+        }
+        assert_eq!(j, metric.len());
+        Depunctured {
+            inner: metric,
+            map: map,
+        }
+    }
+}
+
+/// The metric of the mother code given the metric of the punctured code
+pub struct Depunctured<'a, M: Metric + 'a> {
+    inner: &'a M,
+    /// the position in the punctured code of every bit of the mother code
+    map: Vec<Option<usize>>,
+}
+
+impl<'a, M: Metric> Metric for Depunctured<'a, M> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        // an erasure carries no information, i.e. P(y|x) / P(y) = 1
+        match self.map[i] {
+            Some(j) => self.inner.symbol(j, x),
+            None => 0f64,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+}
+
+/// Perform convolutional encoding with the mother code `gs` followed by puncturing
+pub fn encode(xs: &Vec<u8>, gs: &Gens, pattern: &Pattern) -> Vec<u8> {
+    pattern.puncture(&encode_mother(xs, gs))
+}
+
+/// Perform decoding of a punctured code using the stack algorithm
+pub fn decode(obs: &Vec<u8>, gs: &Gens, p: f64, pattern: &Pattern) -> Vec<u8> {
+    decode_with(&pattern.depuncture(&Bsc::new(obs, p)), gs).0
+}
+
+#[test]
+fn test_puncture() {
+    let ys: Vec<usize> = (0..12).collect();
+    assert_eq!(Pattern::rate_2_3().puncture(&ys), vec![0, 1, 2, 4, 5, 6, 8, 9, 10]);
+    assert_eq!(Pattern::rate_3_4().puncture(&ys), vec![0, 1, 2, 5, 6, 7, 8, 11]);
+
+    let obs = vec![1, 0, 1, 1, 0, 0, 1, 0];
+    let bsc = Bsc::new(&obs, 0.1);
+    let d = Pattern::rate_3_4().depuncture(&bsc);
+    assert_eq!(d.len(), 12);
+    assert_eq!(d.symbol(3, 0), 0f64);
+    assert_eq!(d.symbol(5, 1), bsc.symbol(3, 1));
+
+    assert!(Pattern::new(vec![vec![1, 0], vec![1, 0]]).is_err());
+    assert!(Pattern::new(vec![vec![1, 1], vec![1]]).is_err());
+}
+
+#[test]
+fn test_punctured_system() {
+    use super::parse_gs;
+
+    let gs = parse_gs("0o171,0o133").unwrap();
+    let xs = vec![1, 0, 1, 1, 0, 0, 1, 1, 1, 0, 1, 0];
+    for pattern in &[Pattern::rate_2_3(), Pattern::rate_3_4()] {
+        let mut ys = encode(&xs, &gs, pattern);
+        assert_eq!(ys.len() as f64, ((xs.len() + gs.m) as f64 / pattern.rate(&gs)).ceil());
+        assert_eq!(decode(&ys, &gs, 0.05, pattern), xs);
+
+        ys[5] ^= 1;
+        assert_eq!(decode(&ys, &gs, 0.05, pattern), xs);
+    }
+}
@@ -0,0 +1,161 @@
+//! Exhaustive search for good rate 1/n feedforward codes.
+//!
+//! Every non-catastrophic code of the given memory is ranked by its free distance,
+//! the number of error events at the free distance and its distance profile.
+//! Codes that only differ in the order of their outputs are equivalent and searched once.
+//! Reversing the generators gives the same spectrum but a different profile,
+//! so reversed codes are kept.
+
+use std::thread;
+use std::cmp::Ordering;
+use super::Gens;
+use catastrophic::is_catastrophic;
+use distance::spectrum;
+use profile::distance_profile;
+
+/// A code found by `search`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    /// The generators, the first coefficient is that of x^0
+    pub gs: Vec<Vec<u8>>,
+    pub dfree: usize,
+    /// The number of error events at the free distance
+    pub paths: u64,
+    /// The distance profile, d_0 to d_m
+    pub profile: Vec<usize>,
+}
+
+impl Candidate {
+    /// The generators in the octal notation of `parse_gs`, i.e. `K=3:7,5`
+    pub fn to_gs_string(&self) -> String {
+        let k = self.gs[0].len();
+        let octal: Vec<String> = self.gs
+            .iter()
+            .map(|g| format!("{:o}", g.iter().fold(0u64, |acc, &c| acc << 1 | c as u64)))
+            .collect();
+        format!("K={}:{}", k, octal.join(","))
+    }
+
+    /// Orders better codes first
+    fn rank(&self, other: &Candidate) -> Ordering {
+        other.dfree
+            .cmp(&self.dfree)
+            .then(self.paths.cmp(&other.paths))
+            .then(other.profile.cmp(&self.profile))
+            .then(self.gs.cmp(&other.gs))
+    }
+}
+
+/// The coefficients of a generator whose bit i is the coefficient of x^i
+fn coeffs(g: u64, m: usize) -> Vec<u8> {
+    (0..m + 1).map(|i| ((g >> i) & 1) as u8).collect()
+}
+
+/// All the sets of n generators of memory m in increasing order, every generator
+/// starts with 1 and at least one of them has the coefficient of x^m
+fn generator_sets(n: usize, m: usize) -> Vec<Vec<u64>> {
+    let gens: Vec<u64> = (0..1u64 << m).map(|g| g << 1 | 1).collect();
+    let mut sets: Vec<Vec<u64>> = vec![Vec::new()];
+    for _ in 0..n {
+        let mut next = Vec::new();
+        for set in sets {
+            for &g in gens.iter().filter(|&&g| set.last().map_or(true, |&last| g >= last)) {
+                let mut set = set.clone();
+                set.push(g);
+                next.push(set);
+            }
+        }
+        sets = next;
+    }
+    sets.retain(|set| set.iter().any(|&g| g >> m == 1));
+    sets
+}
+
+/// Evaluates the non-catastrophic codes among the generator sets
+fn evaluate(sets: Vec<Vec<u64>>, m: usize) -> Vec<Candidate> {
+    let mut res = Vec::new();
+    for set in sets {
+        let gs = Gens::new(set.iter().map(|&g| coeffs(g, m)).collect());
+        if is_catastrophic(&gs) {
+            continue;
+        }
+        let spec = spectrum(&gs, 1).unwrap();
+        res.push(Candidate {
+            dfree: spec.dfree,
+            paths: spec.terms[0].paths,
+            profile: distance_profile(&gs),
+            gs: gs.gs,
+        });
+    }
+    res
+}
+
+/// Searches all the rate 1/`n` codes of memory `m` using `threads` threads,
+/// the best codes come first
+pub fn search(n: usize, m: usize, threads: usize) -> Vec<Candidate> {
+    assert!(n > 0 && m > 0 && threads > 0);
+    let mut sets = generator_sets(n, m);
+    let chunk = (sets.len() + threads - 1) / threads;
+
+    let mut handles = Vec::new();
+    while !sets.is_empty() {
+        let rest = sets.split_off(::std::cmp::min(chunk, sets.len()));
+        let part = ::std::mem::replace(&mut sets, rest);
+        handles.push(thread::spawn(move || evaluate(part, m)));
+    }
+
+    let mut res: Vec<Candidate> = handles.into_iter()
+        .flat_map(|h| h.join().unwrap().into_iter())
+        .collect();
+    res.sort_by(|a, b| a.rank(b));
+    res
+}
+
+/// Formats the candidates as a table with one code per line,
+/// the last column can be passed to `parse_gs`
+pub fn to_table(candidates: &[Candidate]) -> String {
+    let mut res = String::from("dfree\tpaths\tprofile\tgenerators\n");
+    for c in candidates {
+        let profile: Vec<String> = c.profile.iter().map(|d| d.to_string()).collect();
+        res.push_str(&format!("{}\t{}\t{}\t{}\n",
+                              c.dfree,
+                              c.paths,
+                              profile.join(","),
+                              c.to_gs_string()));
+    }
+    res
+}
+
+#[test]
+fn test_generator_sets() {
+    // 2 generators of memory 1 starting with 1: 1 and 1 + x, (1, 1) has memory 0
+    assert_eq!(generator_sets(2, 1), vec![vec![1, 3], vec![3, 3]]);
+    assert_eq!(coeffs(0b1011, 3), vec![1, 1, 0, 1]);
+}
+
+#[test]
+fn test_search() {
+    use super::parse_gs;
+
+    let codes = search(2, 2, 3);
+    assert_eq!(codes[0].gs, vec![vec![1, 0, 1], vec![1, 1, 1]]);
+    assert_eq!((codes[0].dfree, codes[0].paths), (5, 1));
+    assert_eq!(codes[0].to_gs_string(), "K=3:5,7");
+    // (1 + x, 1 + x) is catastrophic
+    assert!(codes.iter().all(|c| c.gs != vec![vec![1, 1, 0], vec![1, 1, 0]]));
+    assert_eq!(search(2, 2, 1), codes);
+
+    // the best memory 3 code and its reverse only differ in the profile
+    let codes = search(2, 3, 4);
+    assert_eq!((codes[0].dfree, codes[0].paths), (6, 1));
+    assert_eq!((codes[1].dfree, codes[1].paths), (6, 1));
+    assert_eq!(codes[0].profile, vec![2, 3, 3, 4]);
+    let reversed: Vec<Vec<u8>> = codes[0].gs
+        .iter()
+        .map(|g| g.iter().rev().cloned().collect())
+        .collect();
+    assert_eq!(codes[1].gs, reversed);
+    let table = to_table(&codes);
+    let best = table.lines().nth(1).unwrap().split('\t').last().unwrap();
+    assert_eq!(parse_gs(best).unwrap().gs, codes[0].gs);
+}
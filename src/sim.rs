@@ -0,0 +1,279 @@
+//! Monte Carlo simulation of the bit and frame error rates.
+//!
+//! For every point of a sweep of channel parameters random frames are encoded, sent over the
+//! channel and decoded until enough frame errors are seen or the frame limit is reached.
+
+use std::fmt::Write;
+use rand::Rng;
+use super::{Gens, Limits, DecodeError, encode, decode_stats_with};
+use channel::{self, Channel};
+use metric::Llr;
+use {fano, viterbi, bcjr};
+
+/// The decoder used in a simulation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decoder {
+    /// The stack algorithm, frames that exceed the limits are erased
+    Stack(Limits),
+    /// The Fano algorithm with the given threshold step and limit on the forward moves,
+    /// frames that exceed the limit are erased
+    Fano(f64, Option<usize>),
+    Viterbi,
+    Bcjr,
+}
+
+/// The meaning of the swept channel parameter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sweep {
+    /// The crossover probability p of a BSC
+    Bsc,
+    /// Eb/N0 in dB of BPSK over an AWGN channel
+    Awgn,
+}
+
+/// When to stop simulating a point
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    /// The number of information bits in a frame
+    pub frame_len: usize,
+    /// Stop once this many frames are in error
+    pub target_errors: usize,
+    /// Stop after this many frames even if there are fewer errors
+    pub max_frames: usize,
+}
+
+/// The result of one simulated point
+#[derive(Clone, Debug, PartialEq)]
+pub struct Point {
+    /// The channel parameter
+    pub param: f64,
+    pub frames: usize,
+    /// Frames with at least one bit error, including erasures
+    pub frame_errors: usize,
+    /// Frames the decoder gave up on, all their bits are counted as errors
+    pub erasures: usize,
+    pub bits: usize,
+    pub bit_errors: usize,
+    /// The total computation, i.e. node extensions for the stack algorithm,
+    /// forward moves for the Fano algorithm and trellis branches for Viterbi and BCJR
+    pub computation: usize,
+}
+
+impl Point {
+    pub fn ber(&self) -> f64 {
+        self.bit_errors as f64 / self.bits as f64
+    }
+
+    pub fn fer(&self) -> f64 {
+        self.frame_errors as f64 / self.frames as f64
+    }
+
+    /// The 95% confidence interval of the bit error rate, treating the bits as independent
+    pub fn ber_interval(&self) -> (f64, f64) {
+        wilson(self.bit_errors, self.bits)
+    }
+
+    /// The 95% confidence interval of the frame error rate
+    pub fn fer_interval(&self) -> (f64, f64) {
+        wilson(self.frame_errors, self.frames)
+    }
+
+    /// The average computation per decoded information bit
+    pub fn computation_per_bit(&self) -> f64 {
+        self.computation as f64 / self.bits as f64
+    }
+}
+
+/// The Wilson score interval of a binomial proportion at 95% confidence
+fn wilson(errors: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0f64, 1f64);
+    }
+    let z = 1.959964f64;
+    let (n, p) = (trials as f64, errors as f64 / trials as f64);
+    let denom = 1f64 + z * z / n;
+    let centre = (p + z * z / (2f64 * n)) / denom;
+    let half = z * (p * (1f64 - p) / n + z * z / (4f64 * n * n)).sqrt() / denom;
+    ((centre - half).max(0f64), (centre + half).min(1f64))
+}
+
+/// Simulates every channel parameter in `params`, the messages and the noise are drawn from `rng`
+pub fn simulate<R: Rng>(gs: &Gens,
+                        decoder: Decoder,
+                        sweep: Sweep,
+                        params: &[f64],
+                        config: &Config,
+                        rng: &mut R)
+                        -> Vec<Point> {
+    assert_eq!(config.frame_len % gs.k, 0);
+    let rate = gs.k as f64 / gs.n as f64;
+    params.iter()
+        .map(|&param| match sweep {
+            Sweep::Bsc => {
+                let mut bsc = channel::Bsc::new(param);
+                simulate_point(gs, decoder, &mut bsc, param, config, rng)
+            }
+            Sweep::Awgn => {
+                let mut awgn = channel::Awgn::new(param, rate);
+                simulate_point(gs, decoder, &mut awgn, param, config, rng)
+            }
+        })
+        .collect()
+}
+
+fn simulate_point<C: Channel, R: Rng>(gs: &Gens,
+                                      decoder: Decoder,
+                                      channel: &mut C,
+                                      param: f64,
+                                      config: &Config,
+                                      rng: &mut R)
+                                      -> Point {
+    let mut point = Point {
+        param: param,
+        frames: 0,
+        frame_errors: 0,
+        erasures: 0,
+        bits: 0,
+        bit_errors: 0,
+        computation: 0,
+    };
+    // every branch of the trellis, including the tail
+    let trellis = (config.frame_len / gs.k + gs.m) * gs.states() * gs.inputs();
+
+    while point.frame_errors < config.target_errors && point.frames < config.max_frames {
+        let xs: Vec<u8> = (0..config.frame_len).map(|_| rng.gen::<bool>() as u8).collect();
+        let ys = channel.transmit(&encode(&xs, gs), rng);
+        let llrs = channel.llrs(&ys);
+        let metric = Llr::new(&llrs);
+
+        let decoded = match decoder {
+            Decoder::Stack(ref limits) => {
+                match decode_stats_with(&metric, gs, limits) {
+                    Ok((zs, stats)) => {
+                        point.computation += stats.extensions;
+                        Some(zs)
+                    }
+                    Err(DecodeError::Overflow { extensions, .. }) => {
+                        point.computation += extensions;
+                        None
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            Decoder::Fano(delta, max_forward) => {
+                match fano::decode_limited_with(&metric, gs, delta, max_forward) {
+                    Ok((zs, stats)) => {
+                        point.computation += stats.forward;
+                        Some(zs)
+                    }
+                    Err(DecodeError::Overflow { extensions, .. }) => {
+                        point.computation += extensions;
+                        None
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            Decoder::Viterbi => {
+                point.computation += trellis;
+                Some(viterbi::decode_with(&metric, gs))
+            }
+            Decoder::Bcjr => {
+                point.computation += trellis;
+                Some(bcjr::hard_decision(&bcjr::decode_with(&metric, gs)))
+            }
+        };
+
+        let errors = match decoded {
+            Some(zs) => zs.iter().zip(xs.iter()).filter(|&(z, x)| z != x).count(),
+            None => {
+                point.erasures += 1;
+                xs.len()
+            }
+        };
+        point.frames += 1;
+        point.bits += xs.len();
+        point.bit_errors += errors;
+        if errors > 0 {
+            point.frame_errors += 1;
+        }
+    }
+    point
+}
+
+/// Formats the points as CSV with a header line
+pub fn to_csv(points: &[Point]) -> String {
+    let mut res = String::from("param,frames,frame_errors,erasures,bits,bit_errors,ber,ber_low,\
+                                ber_high,fer,fer_low,fer_high,computation_per_bit\n");
+    for p in points {
+        let (ber_low, ber_high) = p.ber_interval();
+        let (fer_low, fer_high) = p.fer_interval();
+        writeln!(res,
+                 "{},{},{},{},{},{},{:e},{:e},{:e},{:e},{:e},{:e},{}",
+                 p.param,
+                 p.frames,
+                 p.frame_errors,
+                 p.erasures,
+                 p.bits,
+                 p.bit_errors,
+                 p.ber(),
+                 ber_low,
+                 ber_high,
+                 p.fer(),
+                 fer_low,
+                 fer_high,
+                 p.computation_per_bit())
+            .unwrap();
+    }
+    res
+}
+
+#[test]
+fn test_wilson() {
+    let (low, high) = wilson(10, 100);
+    assert!((low - 0.0552).abs() < 1e-3 && (high - 0.1744).abs() < 1e-3);
+    assert_eq!(wilson(0, 100).0, 0f64);
+    assert!(wilson(0, 100).1 > 0f64);
+}
+
+#[test]
+fn test_simulate() {
+    use super::seeded_rng;
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let config = Config {
+        frame_len: 50,
+        target_errors: 10,
+        max_frames: 200,
+    };
+    let limits = Limits {
+        max_extensions: Some(2000),
+        max_stack: None,
+    };
+    let params = [0.1, 0.01];
+    let decoders = [Decoder::Stack(limits),
+                    Decoder::Fano(1f64, Some(10_000)),
+                    Decoder::Viterbi,
+                    Decoder::Bcjr];
+    for &decoder in &decoders {
+        let points = simulate(&gs, decoder, Sweep::Bsc, &params, &config, &mut seeded_rng(18));
+        assert_eq!(points.len(), 2);
+        // a noisier channel reaches the target errors sooner
+        assert!(points[0].fer() > points[1].fer());
+        assert!(points[0].frames < points[1].frames);
+        assert!(points[0].frame_errors >= 10 || points[0].frames == 200);
+        let (low, high) = points[0].ber_interval();
+        assert!(low <= points[0].ber() && points[0].ber() <= high);
+    }
+
+    // a frame needs at least one forward move per branch, so every frame is erased
+    let fano = Decoder::Fano(1f64, Some(10));
+    let points = simulate(&gs, fano, Sweep::Bsc, &[0.01], &config, &mut seeded_rng(18));
+    assert_eq!(points[0].erasures, points[0].frames);
+    assert_eq!(points[0].bit_errors, points[0].bits);
+
+    let mut rng = seeded_rng(18);
+    let points = simulate(&gs, Decoder::Viterbi, Sweep::Awgn, &[0f64, 6f64], &config, &mut rng);
+    assert!(points[0].ber() > points[1].ber());
+    let csv = to_csv(&points);
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.lines().nth(1).unwrap().starts_with("0,"));
+}
@@ -0,0 +1,248 @@
+//! Streaming stack decoding with a bounded decision delay.
+//!
+//! The received symbols are fed incrementally and the decoder releases the
+//! first undecided input symbol of the best path once that path is more than
+//! `depth` branches ahead of it. Paths that disagree with the decision are
+//! dropped and the rest only keep their undecided bits, so together with the
+//! bounded stack the memory does not grow with the length of the stream.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use super::{Gens, next_state_word, branch_metric, push_input};
+use bitvec::BitVec;
+use metric::Metric;
+
+/// A stack decoder for unbounded streams of feedforward or recursive codes without a tail
+pub struct StreamDecoder<'a> {
+    gs: &'a Gens,
+    /// the decision depth in branches
+    depth: usize,
+    /// the maximum number of paths kept on the stack
+    max_stack: usize,
+    /// the paths ordered by their metric, so both the best and the worst are at hand
+    stack: BTreeMap<Key, Node>,
+    /// the number of paths pushed so far, breaks ties between equal metrics
+    pushed: u64,
+    /// the number of branches that are decided
+    decided: usize,
+    /// the metrics of the undecided received symbols
+    buffer: Buffer,
+}
+
+impl<'a> StreamDecoder<'a> {
+    /// Create a decoder that starts in the zero state
+    pub fn new(gs: &'a Gens, depth: usize, max_stack: usize) -> StreamDecoder<'a> {
+        assert!(depth > 0 && max_stack > 0);
+        let mut dec = StreamDecoder {
+            gs: gs,
+            depth: depth,
+            max_stack: max_stack,
+            stack: BTreeMap::new(),
+            pushed: 0,
+            decided: 0,
+            buffer: Buffer {
+                offset: 0,
+                symbols: VecDeque::new(),
+            },
+        };
+        dec.insert(Node {
+            path: BitVec::new(),
+            mu: 0f64,
+            state: 0,
+        });
+        dec
+    }
+
+    /// The number of decided input symbols
+    pub fn decided(&self) -> usize {
+        self.decided
+    }
+
+    /// The number of paths on the stack
+    pub fn stack_len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Feed the next received symbols described by `metric`,
+    /// returns the bits that are decided as a result.
+    pub fn push<M: Metric>(&mut self, metric: &M) -> Vec<u8> {
+        for i in 0..metric.len() {
+            self.buffer.symbols.push_back([metric.symbol(i, 0), metric.symbol(i, 1)]);
+        }
+
+        let mut res = Vec::new();
+        while self.extend_best() {
+            // release the oldest branch of the best path once it is old enough
+            if self.best().path.len() / self.gs.k > self.depth {
+                res.extend(self.decide());
+            }
+        }
+        res
+    }
+
+    /// Decode the remaining symbols and release all the undecided bits of the best path,
+    /// the tail inputs are included if the stream was terminated.
+    pub fn finish(mut self) -> Vec<u8> {
+        while self.extend_best() {}
+        self.pop_best().path.to_bits()
+    }
+
+    /// Extends the best path if its next branch has been received
+    fn extend_best(&mut self) -> bool {
+        let n = self.gs.n;
+        let idx = self.decided + self.best().path.len() / self.gs.k;
+        if (idx + 1) * n > self.buffer.len() {
+            return false;
+        }
+
+        let best = self.pop_best();
+        for u in 0..self.gs.inputs() {
+            let (state, cs) = next_state_word(best.state, u, self.gs);
+            let mut path = best.path.clone();
+            path.push_word(u as u64, self.gs.k);
+            let mu = best.mu + branch_metric(cs, idx, &self.buffer, self.gs);
+            self.insert(Node {
+                path: path,
+                mu: mu,
+                state: state,
+            });
+        }
+        true
+    }
+
+    fn best(&self) -> &Node {
+        self.stack.values().next_back().unwrap()
+    }
+
+    fn pop_best(&mut self) -> Node {
+        let key = *self.stack.keys().next_back().unwrap();
+        self.stack.remove(&key).unwrap()
+    }
+
+    /// Pushes `node` and drops the worst path if the stack is full
+    fn insert(&mut self, node: Node) {
+        let key = Key {
+            mu: node.mu,
+            seq: self.pushed,
+        };
+        self.pushed += 1;
+        self.stack.insert(key, node);
+        if self.stack.len() > self.max_stack {
+            let worst = *self.stack.keys().next().unwrap();
+            self.stack.remove(&worst);
+        }
+    }
+
+    /// Releases the oldest undecided input symbol of the best path and
+    /// drops the paths that do not agree with it
+    fn decide(&mut self) -> Vec<u8> {
+        let k = self.gs.k;
+        let decision = self.best().path.word(0, k);
+        self.stack = ::std::mem::replace(&mut self.stack, BTreeMap::new())
+            .into_iter()
+            .filter(|&(_, ref node)| node.path.len() >= k && node.path.word(0, k) == decision)
+            .map(|(key, mut node)| {
+                node.path.remove_front(k);
+                (key, node)
+            })
+            .collect();
+
+        // the symbols of the decided branch are no longer needed
+        self.decided += 1;
+        for _ in 0..self.gs.n {
+            self.buffer.symbols.pop_front();
+        }
+        self.buffer.offset += self.gs.n;
+        let mut res = Vec::with_capacity(k);
+        push_input(&mut res, decision as usize, k);
+        res
+    }
+}
+
+/// The received symbols that are still needed, indexed from the start of the stream
+struct Buffer {
+    offset: usize,
+    /// the metric of every symbol for the code bits 0 and 1
+    symbols: VecDeque<[f64; 2]>,
+}
+
+impl Metric for Buffer {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        self.symbols[i - self.offset][x as usize]
+    }
+
+    fn len(&self) -> usize {
+        self.offset + self.symbols.len()
+    }
+}
+
+/// An undecided path, `path` only holds the bits after the decided branches
+struct Node {
+    path: BitVec,
+    mu: f64,
+    state: usize,
+}
+
+/// The position of a path on the stack, ordered by the metric. Among equal metrics the path
+/// that was pushed first is the better one, so ties favour input 0 like the other decoders.
+#[derive(Clone, Copy, Debug)]
+struct Key {
+    mu: f64,
+    seq: u64,
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Key) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Key) -> Ordering {
+        self.mu.partial_cmp(&other.mu).unwrap().then(other.seq.cmp(&self.seq))
+    }
+}
+
+#[test]
+fn test_stream_decoder() {
+    use encoder::Encoder;
+    use metric::Bsc;
+
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let xs: Vec<u8> = (0..300).map(|i| ((i * 7 + i / 3) % 2) as u8).collect();
+    let mut enc = Encoder::new(&gs);
+    let mut ys = enc.push_bits(&xs);
+    ys.extend(enc.flush());
+
+    // a stack of one path only keeps the best extension, which is enough without errors
+    let mut dec = StreamDecoder::new(&gs, 15, 1);
+    let mut res = dec.push(&Bsc::new(&ys, 0.05));
+    assert_eq!(dec.stack_len(), 1);
+    res.extend(dec.finish());
+    assert_eq!(&res[..xs.len()], &xs[..]);
+
+    for i in (17..ys.len()).filter(|i| i % 53 == 0) {
+        ys[i] ^= 1;
+    }
+
+    let mut dec = StreamDecoder::new(&gs, 15, 64);
+    let mut res = Vec::new();
+    for chunk in ys.chunks(10) {
+        let decided = dec.push(&Bsc::new(chunk, 0.05));
+        res.extend(decided);
+        assert!(dec.stack_len() <= 64);
+        assert_eq!(res.len(), dec.decided());
+    }
+    assert!(res.len() >= xs.len() - 15);
+    res.extend(dec.finish());
+    res.truncate(xs.len());
+    assert_eq!(res, xs);
+}
@@ -0,0 +1,124 @@
+//! Tail-biting encoding and decoding.
+//!
+//! Instead of flushing the registers with m tail inputs, a tail-biting encoder
+//! starts in the state given by the last m inputs, so that it also ends there.
+//! This saves the rate loss of the tail, which matters for short frames, but the
+//! decoder no longer knows the start and end state. The decoder below performs
+//! a circular stack search: there is a root for every start state and the last m
+//! inputs of every path are forced to bring the encoder back to its start state.
+
+use std::collections::BinaryHeap;
+use super::{Gens, CodePath, encode_from};
+use bitvec::BitVec;
+use metric::{Metric, Bsc};
+
+/// Perform tail-biting convolutional encoding, the encoder must not be recursive
+/// and the message must have at least m inputs
+pub fn encode(xs: &Vec<u8>, gs: &Gens) -> Vec<u8> {
+    assert!(gs.fb.is_none(), "Tail-biting is not supported for recursive encoders");
+    assert!(xs.len() / gs.k >= gs.m);
+
+    // for feedforward encoders the final state only depends on the last m inputs
+    let (state, _) = encode_from(0, xs, gs);
+    encode_from(state, xs, gs).1
+}
+
+/// The input symbols allowed at depth `t` of a path of `l` branches which starts in `start`,
+/// the inputs that end up in the final state must match the start state.
+fn allowed_inputs(start: usize, t: usize, l: usize, gs: &Gens) -> Vec<usize> {
+    let mut mask = 0;
+    let mut forced = 0;
+    let mut offset = 0;
+    for (i, &m) in gs.ms.iter().enumerate() {
+        let j = l - t; // the input of time t is x_{l-j} at the end
+        if j <= m {
+            mask |= 1 << i;
+            forced |= ((start >> (offset + j - 1)) & 1) << i;
+        }
+        offset += m;
+    }
+    (0..gs.inputs()).filter(|u| u & mask == forced).collect()
+}
+
+/// Same as `decode` but returns a tuple of the result and the intermediate progress
+pub fn decode_(obs: &Vec<u8>, gs: &Gens, p: f64) -> (Vec<u8>, Vec<CodePath>) {
+    decode_with(&Bsc::new(obs, p), gs)
+}
+
+/// Same as `decode_` but the channel is described by an arbitrary metric
+pub fn decode_with<M: Metric>(metric: &M, gs: &Gens) -> (Vec<u8>, Vec<CodePath>) {
+    assert!(gs.fb.is_none(), "Tail-biting is not supported for recursive encoders");
+    let mut heap = BinaryHeap::new();
+    let l = metric.len() / gs.n;
+    let mut progress = Vec::new();
+
+    // one root for every possible start state
+    for s in 0..gs.states() {
+        heap.push(TailBitingPath {
+            start: s,
+            path: CodePath {
+                path: BitVec::new(),
+                code: BitVec::new(),
+                mu: 0f64,
+                state: s,
+            },
+        });
+    }
+
+    // loop until a complete path is found
+    loop {
+        let best = heap.pop().unwrap();
+        let t = best.path.path.len() / gs.k;
+        if t >= l {
+            progress.push(best.path.clone());
+            return (best.path.path.to_bits(), progress);
+        }
+
+        for u in allowed_inputs(best.start, t, l, gs) {
+            let mut path = best.path.clone();
+            path.fano(u, metric, gs);
+            progress.push(path.clone());
+            heap.push(TailBitingPath {
+                start: best.start,
+                path: path,
+            });
+        }
+    }
+}
+
+/// Perform tail-biting decoding using the circular stack search
+pub fn decode(obs: &Vec<u8>, gs: &Gens, p: f64) -> Vec<u8> {
+    decode_(obs, gs, p).0
+}
+
+/// A path in the tree together with its start state
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct TailBitingPath {
+    path: CodePath,
+    start: usize,
+}
+
+#[test]
+fn test_tailbiting_encode() {
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let xs = vec![1, 0, 1, 1];
+    assert_eq!(encode(&xs, &gs), vec![1, 0, 0, 1, 0, 0, 0, 1]);
+
+    // only the last two inputs must match the start state 3
+    assert_eq!(allowed_inputs(3, 1, 4, &gs), vec![0, 1]);
+    assert_eq!(allowed_inputs(3, 2, 4, &gs), vec![1]);
+    assert_eq!(allowed_inputs(2, 3, 4, &gs), vec![0]);
+}
+
+#[test]
+fn test_tailbiting_system() {
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let xs = vec![1, 1, 0, 1, 0, 0, 1, 0, 1, 1, 1, 0];
+    let mut ys = encode(&xs, &gs);
+    assert_eq!(ys.len(), xs.len() * gs.n);
+    assert_eq!(decode(&ys, &gs, 0.05), xs);
+
+    ys[0] ^= 1;
+    ys[20] ^= 1;
+    assert_eq!(decode(&ys, &gs, 0.05), xs);
+}
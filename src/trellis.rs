@@ -0,0 +1,217 @@
+//! An explicit trellis of a convolutional code.
+//!
+//! The structure of a code is implicit in its generators, `next_state` shifts the registers
+//! and sums the taps on every branch. The trellis does this once for every state and input
+//! symbol and stores the next state and the n output bits in a table. The table grows with
+//! the number of states, so the tree search decoders that handle large memories keep
+//! working on the generators directly.
+//!
+//! The states are numbered `0..states()` with the register layout of `next_state`.
+
+use std::ops::Range;
+use super::{Gens, next_state};
+
+/// A transition of the trellis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Branch<'a> {
+    pub from: usize,
+    /// The input symbol, bit i is the bit of input i
+    pub input: usize,
+    pub to: usize,
+    /// The n output bits
+    pub output: &'a [u8],
+}
+
+impl<'a> Branch<'a> {
+    /// The number of nonzero output bits
+    pub fn weight(&self) -> usize {
+        self.output.iter().filter(|&&c| c == 1).count()
+    }
+
+    /// The number of nonzero input bits
+    pub fn input_weight(&self) -> usize {
+        self.input.count_ones() as usize
+    }
+}
+
+/// The state-transition table of a feedforward or recursive encoder
+#[derive(Clone, Debug)]
+pub struct Trellis {
+    pub n: usize,
+    pub k: usize,
+    /// The largest memory out of all the inputs, i.e. the length of the tail
+    pub m: usize,
+    states: usize,
+    /// `next[s * inputs + u]` is the state after input symbol u in state s
+    next: Vec<usize>,
+    /// the n output bits of every transition, in the order of `next`
+    outputs: Vec<u8>,
+    /// the input symbol of the terminating tail of every state
+    tail: Vec<usize>,
+}
+
+impl Trellis {
+    /// Tabulates the transitions of the encoder described by `gs`
+    pub fn new(gs: &Gens) -> Trellis {
+        let states = gs.states();
+        let mut next = Vec::with_capacity(states * gs.inputs());
+        let mut outputs = Vec::with_capacity(states * gs.inputs() * gs.n);
+        for s in 0..states {
+            for u in 0..gs.inputs() {
+                let (t, cs) = next_state(s, u, gs);
+                next.push(t);
+                outputs.extend(cs);
+            }
+        }
+        Trellis {
+            n: gs.n,
+            k: gs.k,
+            m: gs.m,
+            states: states,
+            next: next,
+            outputs: outputs,
+            tail: (0..states).map(|s| gs.tail_input(s)).collect(),
+        }
+    }
+
+    /// The number of states
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// The number of input symbols, i.e. the number of branches leaving every state
+    pub fn inputs(&self) -> usize {
+        1 << self.k
+    }
+
+    /// The state after the input symbol `u` in `state`
+    pub fn next_state(&self, state: usize, u: usize) -> usize {
+        self.next[self.index(state, u)]
+    }
+
+    /// The n output bits of the input symbol `u` in `state`
+    pub fn output(&self, state: usize, u: usize) -> &[u8] {
+        let idx = self.index(state, u);
+        &self.outputs[idx * self.n..(idx + 1) * self.n]
+    }
+
+    /// The input symbol of the terminating tail, see `Gens::tail_input`
+    pub fn tail_input(&self, state: usize) -> usize {
+        self.tail[state]
+    }
+
+    /// The transition of the input symbol `u` in `state`
+    pub fn branch<'a>(&'a self, state: usize, u: usize) -> Branch<'a> {
+        self.to_branch(self.index(state, u))
+    }
+
+    /// The branches leaving `state` in the order of their input symbols
+    pub fn branches<'a>(&'a self, state: usize) -> Branches<'a> {
+        let idx = self.index(state, 0);
+        Branches {
+            trellis: self,
+            range: idx..idx + self.inputs(),
+        }
+    }
+
+    /// Same as `branches`, but in the tail only the terminating branch is left
+    pub fn branches_at<'a>(&'a self, state: usize, tail: bool) -> Branches<'a> {
+        if tail {
+            let idx = self.index(state, self.tail[state]);
+            Branches {
+                trellis: self,
+                range: idx..idx + 1,
+            }
+        } else {
+            self.branches(state)
+        }
+    }
+
+    /// All the branches of a trellis section, ordered by state and input symbol
+    pub fn all_branches<'a>(&'a self) -> Branches<'a> {
+        Branches {
+            trellis: self,
+            range: 0..self.next.len(),
+        }
+    }
+
+    fn index(&self, state: usize, u: usize) -> usize {
+        assert!(state < self.states && u < self.inputs());
+        state * self.inputs() + u
+    }
+
+    fn to_branch<'a>(&'a self, idx: usize) -> Branch<'a> {
+        Branch {
+            from: idx / self.inputs(),
+            input: idx % self.inputs(),
+            to: self.next[idx],
+            output: &self.outputs[idx * self.n..(idx + 1) * self.n],
+        }
+    }
+}
+
+/// An iterator over branches of a `Trellis`
+#[derive(Clone, Debug)]
+pub struct Branches<'a> {
+    trellis: &'a Trellis,
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for Branches<'a> {
+    type Item = Branch<'a>;
+
+    fn next(&mut self) -> Option<Branch<'a>> {
+        self.range.next().map(|idx| self.trellis.to_branch(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+#[test]
+fn test_trellis() {
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let trellis = Trellis::new(&gs);
+    assert_eq!((trellis.states(), trellis.inputs()), (4, 2));
+    assert_eq!(trellis.all_branches().count(), 8);
+    // input 1 in state 0 outputs the first coefficients of the generators
+    assert_eq!(trellis.branch(0, 1),
+               Branch {
+                   from: 0,
+                   input: 1,
+                   to: 1,
+                   output: &[1, 1],
+               });
+    let tos: Vec<usize> = trellis.branches(3).map(|b| b.to).collect();
+    assert_eq!(tos, vec![2, 3]);
+    assert_eq!(trellis.branches_at(3, true).map(|b| b.input).collect::<Vec<_>>(), vec![0]);
+    for b in trellis.all_branches() {
+        assert_eq!((b.to, b.output.to_vec()), next_state(b.from, b.input, &gs));
+    }
+}
+
+#[test]
+fn test_trellis_encode() {
+    use super::{encode, to_input};
+
+    // walking the trellis is the same as encoding, including the tail of recursive encoders
+    let xs = vec![1, 0, 1, 1, 0, 0, 1, 0];
+    for gs in vec![Gens::systematic(vec![1, 1, 1], vec![vec![1, 0, 1]]),
+                   Gens::with_inputs(vec![vec![vec![1, 1], vec![0, 1], vec![1, 1]],
+                                          vec![vec![0, 1, 1], vec![1, 0, 0], vec![1, 0, 1]]])] {
+        let trellis = Trellis::new(&gs);
+        let (mut s, mut ys) = (0, Vec::new());
+        for u in xs.chunks(gs.k).map(to_input) {
+            ys.extend_from_slice(trellis.output(s, u));
+            s = trellis.next_state(s, u);
+        }
+        for _ in 0..trellis.m {
+            let b = trellis.branches_at(s, true).next().unwrap();
+            ys.extend_from_slice(b.output);
+            s = b.to;
+        }
+        assert_eq!(s, 0);
+        assert_eq!(ys, encode(&xs, &gs));
+    }
+}
@@ -0,0 +1,170 @@
+//! Parallel concatenated (turbo) codes.
+//!
+//! The information bits are encoded by a recursive systematic code, and the interleaved
+//! information bits by a second copy of it. Only the parity of the second encoder is sent
+//! besides the tails, which gives a rate of about 1/3 for a rate 1/2 constituent code.
+//! A frame of L bits is sent as L branches of (x, parity 1, parity 2) followed by
+//! the terminating tails of both encoders.
+//!
+//! The decoder runs BCJR on both constituent codes in turn, each using the extrinsic
+//! information of the other one as a priori information.
+
+use std::f64;
+use super::{Gens, encode as encode_rsc};
+use bcjr;
+use interleave::Interleaver;
+use metric::{Metric, Bsc};
+
+/// A turbo code built from two copies of a rate 1/2 recursive systematic code
+pub struct Turbo<I: Interleaver> {
+    pub gs: Gens,
+    pub interleaver: I,
+}
+
+impl<I: Interleaver> Turbo<I> {
+    /// `gs` must be created with `Gens::systematic` with a single parity polynomial
+    /// and the interleaver must not add any symbols
+    pub fn new(gs: Gens, interleaver: I) -> Turbo<I> {
+        assert!(gs.k == 1 && gs.n == 2 && gs.fb.is_some());
+        // the first output is sent as the information bit, so it must be the input itself
+        assert!(gs.gs[0] == *gs.fb.as_ref().unwrap(),
+                "The constituent code must be systematic");
+        assert_eq!(interleaver.delay(), 0);
+        Turbo {
+            gs: gs,
+            interleaver: interleaver,
+        }
+    }
+
+    /// The length of the code of `len` information bits
+    pub fn code_len(&self, len: usize) -> usize {
+        3 * len + 4 * self.gs.m
+    }
+
+    /// Encodes the information bits `xs`
+    pub fn encode(&self, xs: &[u8]) -> Vec<u8> {
+        let (l, m) = (xs.len(), self.gs.m);
+        let ys1 = encode_rsc(&xs.to_vec(), &self.gs);
+        let ys2 = encode_rsc(&self.interleaver.interleave(xs), &self.gs);
+
+        let mut res = Vec::with_capacity(self.code_len(l));
+        for t in 0..l {
+            res.extend_from_slice(&[xs[t], ys1[2 * t + 1], ys2[2 * t + 1]]);
+        }
+        res.extend_from_slice(&ys1[2 * l..2 * (l + m)]);
+        res.extend_from_slice(&ys2[2 * l..2 * (l + m)]);
+        res
+    }
+
+    /// Returns the hard decisions of a hard-decision received word after `iterations` iterations
+    pub fn decode(&self, obs: &[u8], p: f64, iterations: usize) -> Vec<u8> {
+        bcjr::hard_decision(&self.decode_with(&Bsc::new(obs, p), iterations))
+    }
+
+    /// Returns the posterior LLRs of the information bits after `iterations` iterations
+    pub fn decode_with<M: Metric>(&self, metric: &M, iterations: usize) -> Vec<f64> {
+        let m = self.gs.m;
+        assert!(metric.len() >= 4 * m && (metric.len() - 4 * m) % 3 == 0);
+        let l = (metric.len() - 4 * m) / 3;
+
+        // the inverse of the interleaver gives the systematic bits of the second encoder
+        let mut inv = vec![0; l];
+        for i in 0..l {
+            inv[self.interleaver.position(i, l)] = i;
+        }
+        let tail = |offset: usize| (0..2 * m).map(move |i| 3 * l + offset + i);
+        let map1 = (0..l).flat_map(|t| vec![3 * t, 3 * t + 1]).chain(tail(0)).collect();
+        let map2 = (0..l).flat_map(|t| vec![3 * inv[t], 3 * t + 2]).chain(tail(2 * m)).collect();
+        let (dec1, dec2) = (Component::new(metric, map1), Component::new(metric, map2));
+
+        let channel: Vec<f64> = (0..l)
+            .map(|t| (metric.symbol(3 * t, 0) - metric.symbol(3 * t, 1)) * f64::consts::LN_2)
+            .collect();
+        let channel2 = self.interleaver.interleave(&channel);
+
+        let mut apriori = vec![0f64; l];
+        let mut llrs = vec![0f64; l];
+        for _ in 0..iterations {
+            let post1 = bcjr::decode_apriori(&dec1, &self.gs, &apriori);
+            let ext1 = extrinsic(&post1, &apriori, &channel);
+
+            let apriori2 = self.interleaver.interleave(&ext1);
+            let post2 = bcjr::decode_apriori(&dec2, &self.gs, &apriori2);
+            let ext2 = extrinsic(&post2, &apriori2, &channel2);
+
+            apriori = self.interleaver.deinterleave(&ext2);
+            llrs = self.interleaver.deinterleave(&post2);
+        }
+        llrs
+    }
+}
+
+/// The information a decoder adds to its a priori and channel information
+fn extrinsic(post: &[f64], apriori: &[f64], channel: &[f64]) -> Vec<f64> {
+    post.iter()
+        .zip(apriori.iter().zip(channel.iter()))
+        .map(|(p, (a, c))| p - a - c)
+        .collect()
+}
+
+/// The metric of one constituent code within the turbo frame
+struct Component<'a, M: Metric + 'a> {
+    inner: &'a M,
+    /// the position in the turbo frame of every bit of the constituent code
+    map: Vec<usize>,
+}
+
+impl<'a, M: Metric> Component<'a, M> {
+    fn new(inner: &'a M, map: Vec<usize>) -> Component<'a, M> {
+        Component {
+            inner: inner,
+            map: map,
+        }
+    }
+}
+
+impl<'a, M: Metric> Metric for Component<'a, M> {
+    fn symbol(&self, i: usize, x: u8) -> f64 {
+        self.inner.symbol(self.map[i], x)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+}
+
+#[test]
+fn test_turbo_encode() {
+    use interleave::Block;
+
+    let gs = Gens::systematic(vec![1, 1, 1], vec![vec![1, 0, 1]]);
+    let turbo = Turbo::new(gs, Block::new(2, 2));
+    let xs = vec![1, 0, 0, 0];
+    let ys = turbo.encode(&xs);
+    assert_eq!(ys.len(), turbo.code_len(4));
+    // the interleaved input is 1, 0, 0, 0 as well so both parities are the same
+    assert_eq!(ys,
+               vec![1, 1, 1, 0, 1, 1, 0, 1, 1, 0, 0, 0, 1, 0, 1, 1, 1, 0, 1, 1]);
+}
+
+#[test]
+fn test_turbo_system() {
+    use rand::Rng;
+    use super::{seeded_rng, create_noise_with};
+    use interleave::Random;
+
+    let mut rng = seeded_rng(17);
+    let l = 200;
+    let xs: Vec<u8> = (0..l).map(|_| rng.gen::<bool>() as u8).collect();
+    let gs = Gens::systematic(vec![1, 1, 1], vec![vec![1, 0, 1]]);
+    let turbo = Turbo::new(gs, Random::new(l, &mut rng));
+    let p = 0.14;
+    let ys = create_noise_with(&turbo.encode(&xs), p, &mut rng);
+
+    let errors = |zs: &[u8]| zs.iter().zip(xs.iter()).filter(|&(a, b)| a != b).count();
+    let first = errors(&turbo.decode(&ys, p, 1));
+    let last = errors(&turbo.decode(&ys, p, 8));
+    // a single iteration leaves errors that the following iterations correct
+    assert!(first > 0);
+    assert_eq!(last, 0);
+}
@@ -0,0 +1,91 @@
+//! Maximum-likelihood decoding of terminated convolutional codes using the Viterbi algorithm.
+
+use std::f64;
+use super::{Gens, push_input, remove_final_m};
+use metric::{Metric, Bsc};
+use trellis::Trellis;
+
+/// Perform maximum-likelihood decoding using the Viterbi algorithm,
+/// `obs` is expected to be terminated like the output of `encode`.
+pub fn decode(obs: &Vec<u8>, gs: &Gens, p: f64) -> Vec<u8> {
+    decode_with(&Bsc::new(obs, p), gs)
+}
+
+/// Same as `decode` but the channel is described by an arbitrary metric
+pub fn decode_with<M: Metric>(metric: &M, gs: &Gens) -> Vec<u8> {
+    let l = metric.len() / gs.n; // number of branches including the tail
+    let trellis = Trellis::new(gs);
+    let states = trellis.states();
+
+    // the encoder always starts in the zero state
+    let mut metrics = vec![f64::NEG_INFINITY; states];
+    metrics[0] = 0f64;
+
+    // survivors[i][s] is the (previous state, input symbol) of the best path into s at depth i + 1
+    let mut survivors: Vec<Vec<(usize, usize)>> = Vec::with_capacity(l);
+    for i in 0..l {
+
+        let mut next_metrics = vec![f64::NEG_INFINITY; states];
+        let mut next_survivors = vec![(0, 0); states];
+        for s in 0..states {
+            if metrics[s] == f64::NEG_INFINITY {
+                continue;
+            }
+            for b in trellis.branches_at(s, i + gs.m >= l) {
+                let branch: f64 = b.output
+                    .iter()
+                    .enumerate()
+                    .map(|(j, c)| metric.symbol(i * gs.n + j, *c))
+                    .sum();
+                let mu = metrics[s] + branch;
+                if mu > next_metrics[b.to] {
+                    next_metrics[b.to] = mu;
+                    next_survivors[b.to] = (s, b.input);
+                }
+            }
+        }
+        metrics = next_metrics;
+        survivors.push(next_survivors);
+    }
+
+    // the tail forces the encoder back to the zero state, trace back from there
+    let mut us = vec![0; l];
+    let mut s = 0;
+    for i in (0..l).rev() {
+        let (prev, u) = survivors[i][s];
+        us[i] = u;
+        s = prev;
+    }
+
+    let mut path = Vec::with_capacity(l * gs.k);
+    for u in us {
+        push_input(&mut path, u, gs.k);
+    }
+    remove_final_m(path, gs.m * gs.k)
+}
+
+#[test]
+fn test_viterbi_and_stack() {
+    use super::{encode, decode_};
+
+    let obs = vec![0, 0, 1, 0, 0, 1, 0, 1, 1, 1, 0, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 1, 0], vec![1, 0, 1]]);
+    let p = 1f64 / 16f64;
+    assert_eq!(decode(&obs, &gs, p), decode_(&obs, &gs, p).0);
+
+    let xs = vec![1, 0, 1, 1, 0, 0, 1];
+    assert_eq!(decode(&encode(&xs, &gs), &gs, p), xs);
+}
+
+#[test]
+fn test_viterbi_errors() {
+    use super::encode;
+
+    // d_free of (111, 101) is 5 so two errors far apart are always corrected
+    let xs = vec![1, 1, 0, 1, 0, 0, 1, 0, 1, 1];
+    let gs = Gens::new(vec![vec![1, 1, 1], vec![1, 0, 1]]);
+    let mut ys = encode(&xs, &gs);
+    ys[1] ^= 1;
+    ys[14] ^= 1;
+    assert_eq!(decode(&ys, &gs, 0.1), xs);
+}